@@ -0,0 +1,236 @@
+use crate::models::BroadcastErrorCode;
+
+// Reject reasons of bitcoind (as relayed by esplora) and the code they map to. A reason only
+// matches when it is the whole reason code, the part of the message before any details.
+const REJECT_REASONS: &[(&str, BroadcastErrorCode)] = &[
+    (
+        "bad-txns-inputs-missingorspent",
+        BroadcastErrorCode::MissingInputs,
+    ),
+    ("missing-inputs", BroadcastErrorCode::MissingInputs),
+    ("missing inputs", BroadcastErrorCode::MissingInputs),
+    (
+        "transaction already in block chain",
+        BroadcastErrorCode::AlreadyInChain,
+    ),
+    (
+        "transaction outputs already in utxo set",
+        BroadcastErrorCode::AlreadyInChain,
+    ),
+    (
+        "txn-already-in-mempool",
+        BroadcastErrorCode::AlreadyInMempool,
+    ),
+    ("txn-already-known", BroadcastErrorCode::AlreadyInMempool),
+    (
+        "replacement-adds-unconfirmed",
+        BroadcastErrorCode::RbfRejected,
+    ),
+    (
+        "too many potential replacements",
+        BroadcastErrorCode::RbfRejected,
+    ),
+    ("txn-mempool-conflict", BroadcastErrorCode::RbfRejected),
+    (
+        "too-long-mempool-chain",
+        BroadcastErrorCode::TooLongMempoolChain,
+    ),
+    (
+        "mempool min fee not met",
+        BroadcastErrorCode::MempoolMinFeeNotMet,
+    ),
+    (
+        "min relay fee not met",
+        BroadcastErrorCode::MinRelayFeeNotMet,
+    ),
+    ("insufficient fee", BroadcastErrorCode::InsufficientFee),
+    ("mempool full", BroadcastErrorCode::MempoolMinFeeNotMet),
+    ("non-bip68-final", BroadcastErrorCode::NonFinal),
+    ("non-final", BroadcastErrorCode::NonFinal),
+    (
+        "non-mandatory-script-verify-flag",
+        BroadcastErrorCode::NonStandard,
+    ),
+    (
+        "mempool-script-verify-flag-failed",
+        BroadcastErrorCode::NonStandard,
+    ),
+    (
+        "mandatory-script-verify-flag-failed",
+        BroadcastErrorCode::ScriptVerifyFailed,
+    ),
+    ("dust", BroadcastErrorCode::Dust),
+    ("absurdly-high-fee", BroadcastErrorCode::MaxFeeExceeded),
+    ("max-fee-exceeded", BroadcastErrorCode::MaxFeeExceeded),
+    ("scriptpubkey", BroadcastErrorCode::NonStandard),
+    ("scriptsig-size", BroadcastErrorCode::NonStandard),
+    ("scriptsig-not-pushonly", BroadcastErrorCode::NonStandard),
+    ("tx-size", BroadcastErrorCode::NonStandard),
+    ("tx-size-small", BroadcastErrorCode::NonStandard),
+    ("bare-multisig", BroadcastErrorCode::NonStandard),
+    ("multi-op-return", BroadcastErrorCode::NonStandard),
+    ("version", BroadcastErrorCode::NonStandard),
+    ("tx decode failed", BroadcastErrorCode::DecodeFailed),
+    ("txdecodefail", BroadcastErrorCode::DecodeFailed),
+];
+
+// Phrases of the reject details (or of RPC error messages) which are specific enough to be
+// looked for anywhere in the message. Checked before the reason codes, as a rejected
+// replacement is reported with the "insufficient fee" reason.
+const REJECT_DETAILS: &[(&str, BroadcastErrorCode)] = &[
+    ("rejecting replacement", BroadcastErrorCode::RbfRejected),
+    (
+        "fee exceeds maximum configured by user",
+        BroadcastErrorCode::MaxFeeExceeded,
+    ),
+];
+
+// Maps the rejection text returned by the node/provider to a machine readable code
+pub fn classify_reject_reason(reject_reason: &str) -> BroadcastErrorCode {
+    let message = get_reject_message(reject_reason).to_lowercase();
+
+    if let Some((_, code)) = REJECT_DETAILS
+        .iter()
+        .find(|(phrase, _)| message.contains(phrase))
+    {
+        return code.clone();
+    }
+
+    // bitcoind formats rejections as "<reason>, <details>" or "<reason> (<details>)", older
+    // versions prefix them with the numeric reject code, e.g. "64: dust"
+    let reason = message
+        .split([',', '(', '.'])
+        .next()
+        .unwrap_or_default()
+        .trim();
+    let reason = reason
+        .split_once(": ")
+        .filter(|(code, _)| code.chars().all(|c| c.is_ascii_digit()))
+        .map_or(reason, |(_, reason)| reason.trim());
+
+    REJECT_REASONS
+        .iter()
+        .find(|(known_reason, _)| *known_reason == reason)
+        .map(|(_, code)| code.clone())
+        .unwrap_or(BroadcastErrorCode::Unknown)
+}
+
+// Esplora relays the JSON-RPC error of bitcoind, e.g.
+// sendrawtransaction RPC error: {"code":-26,"message":"dust"}
+fn get_reject_message(reject_reason: &str) -> String {
+    reject_reason
+        .find('{')
+        .and_then(|start| {
+            serde_json::Deserializer::from_str(&reject_reason[start..])
+                .into_iter::<serde_json::Value>()
+                .next()
+        })
+        .and_then(Result::ok)
+        .and_then(|error| error.get("message")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| reject_reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_reject_reason() {
+        let cases = [
+            (
+                r#"sendrawtransaction RPC error: {"code":-25,"message":"bad-txns-inputs-missingorspent"}"#,
+                BroadcastErrorCode::MissingInputs,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-27,"message":"Transaction already in block chain"}"#,
+                BroadcastErrorCode::AlreadyInChain,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"min relay fee not met, 100 < 141"}"#,
+                BroadcastErrorCode::MinRelayFeeNotMet,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"insufficient fee, rejecting replacement 5f0a..., not enough additional fees to relay; 0.00 < 0.00000141"}"#,
+                BroadcastErrorCode::RbfRejected,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"too-long-mempool-chain, too many descendants for tx 5f0a... [limit: 25]"}"#,
+                BroadcastErrorCode::TooLongMempoolChain,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"dust"}"#,
+                BroadcastErrorCode::Dust,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"non-final"}"#,
+                BroadcastErrorCode::NonFinal,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"scriptpubkey"}"#,
+                BroadcastErrorCode::NonStandard,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"dust, tx with dust output must be 0-fee"}"#,
+                BroadcastErrorCode::Dust,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"tx-size-small"}"#,
+                BroadcastErrorCode::NonStandard,
+            ),
+            ("64: non-BIP68-final", BroadcastErrorCode::NonFinal),
+            (
+                "Fee exceeds maximum configured by user (e.g. -maxtxfee, maxfeerate)",
+                BroadcastErrorCode::MaxFeeExceeded,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-22,"message":"TX decode failed. Make sure the tx has at least one input."}"#,
+                BroadcastErrorCode::DecodeFailed,
+            ),
+            ("Service Unavailable", BroadcastErrorCode::Unknown),
+        ];
+
+        for (reject_reason, expected_code) in cases {
+            assert_eq!(classify_reject_reason(reject_reason), expected_code);
+        }
+    }
+
+    #[test]
+    fn test_classify_reject_reason_details() {
+        // Reasons mentioned in the details of another rejection do not match
+        let cases = [
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"TRUC-violation, version=3 tx 5f0a... (wtxid=9c1e...) cannot spend from non-version=3 tx 7b2d... (wtxid=3a4f...)"}"#,
+                BroadcastErrorCode::Unknown,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"mandatory-script-verify-flag-failed (Witness requires empty scriptSig)"}"#,
+                BroadcastErrorCode::ScriptVerifyFailed,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"non-mandatory-script-verify-flag (Witness version reserved for soft-fork upgrades)"}"#,
+                BroadcastErrorCode::NonStandard,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"bad-txns-in-belowout, value in (0.00001) < value out (0.00002)"}"#,
+                BroadcastErrorCode::Unknown,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"bad-txns-nonstandard-inputs"}"#,
+                BroadcastErrorCode::Unknown,
+            ),
+            (
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"bad-txns-vout-toolarge"}"#,
+                BroadcastErrorCode::Unknown,
+            ),
+        ];
+
+        for (reject_reason, expected_code) in cases {
+            assert_eq!(
+                classify_reject_reason(reject_reason),
+                expected_code,
+                "{}",
+                reject_reason
+            );
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use bitcoin::{Address, Amount, Network, Transaction, TxOut};
+
+use crate::{
+    blockchains::bitcoin::transaction_decoder::{get_fee, sum_values},
+    btc_api_error::BtcApiError,
+    config::BroadcastPolicyConfig,
+};
+
+// Local sanity checks run before a transaction is handed to the provider, the prevouts have to
+// be in the same order as the inputs. Returns the absolute fee and the fee rate in sat/vB.
+//
+// The fee percent is measured against the payment outputs, the change paying back to a script
+// spent by the inputs does not count as sent. A transaction with only change outputs, e.g. a
+// consolidation, is measured against all of its outputs.
+pub fn check_broadcast_policy(
+    txn: &Transaction,
+    prevouts: &[TxOut],
+    policy: &BroadcastPolicyConfig,
+) -> Result<(Amount, f64), BtcApiError> {
+    let fee = get_fee(txn, prevouts)?.ok_or_else(|| {
+        BtcApiError::BroadcastPolicyViolation(
+            "Outputs are worth more than the spent inputs".to_string(),
+        )
+    })?;
+
+    let fee_rate = fee.to_sat() as f64 / txn.vsize() as f64;
+    if fee_rate > policy.max_fee_rate {
+        return Err(BtcApiError::BroadcastPolicyViolation(format!(
+            "Fee rate {:.2} sat/vB is above the maximum of {} sat/vB",
+            fee_rate, policy.max_fee_rate
+        )));
+    }
+
+    let payment_outputs = txn
+        .output
+        .iter()
+        .filter(|output| {
+            !prevouts
+                .iter()
+                .any(|prevout| prevout.script_pubkey == output.script_pubkey)
+        })
+        .cloned()
+        .collect::<Vec<TxOut>>();
+    let sent_amount = if payment_outputs.is_empty() {
+        sum_values(&txn.output)?
+    } else {
+        sum_values(&payment_outputs)?
+    };
+
+    let fee_percent = fee.to_sat() as f64 * 100.0 / sent_amount.to_sat().max(1) as f64;
+    if fee_percent > policy.max_fee_percent {
+        return Err(BtcApiError::BroadcastPolicyViolation(format!(
+            "Fee {} is {:.2}% of the sent amount {}, above the maximum of {}%",
+            fee, fee_percent, sent_amount, policy.max_fee_percent
+        )));
+    }
+
+    for (vout, output) in txn.output.iter().enumerate() {
+        // Data carrier outputs have no address and are expected to be worth nothing
+        if output.script_pubkey.is_op_return() {
+            continue;
+        }
+
+        // Scripts carry no network, any network only tells whether they have an address form
+        if Address::from_script(&output.script_pubkey, Network::Bitcoin).is_err() {
+            return Err(BtcApiError::BroadcastPolicyViolation(format!(
+                "Output {} is not a standard output",
+                vout
+            )));
+        }
+
+        let dust_limit = output.script_pubkey.minimal_non_dust();
+        if output.value < dust_limit {
+            return Err(BtcApiError::BroadcastPolicyViolation(format!(
+                "Output {} of {} is below the dust limit of {}",
+                vout, output.value, dust_limit
+            )));
+        }
+    }
+
+    Ok((fee, fee_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        absolute::LockTime, hashes::Hash, transaction::Version, OutPoint, ScriptBuf, Sequence,
+        TxIn, WPubkeyHash, Witness,
+    };
+
+    use super::*;
+
+    fn transaction(output_values: &[u64]) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::from_slice(&[vec![0x30; 71], vec![0x02; 33]]),
+            }],
+            output: output_values
+                .iter()
+                .map(|value| TxOut {
+                    value: Amount::from_sat(*value),
+                    script_pubkey: ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array([7; 20])),
+                })
+                .collect(),
+        }
+    }
+
+    fn prevouts(value: u64) -> Vec<TxOut> {
+        vec![TxOut {
+            value: Amount::from_sat(value),
+            script_pubkey: ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array([7; 20])),
+        }]
+    }
+
+    #[test]
+    fn test_check_broadcast_policy() {
+        let policy = BroadcastPolicyConfig {
+            max_fee_rate: 100.0,
+            max_fee_percent: 5.0,
+        };
+
+        // 1000 sats fee on a 141 vB transaction
+        let txn = transaction(&[90_000, 9_000]);
+        let (fee, fee_rate) = check_broadcast_policy(&txn, &prevouts(100_000), &policy).unwrap();
+        assert_eq!(fee, Amount::from_sat(1_000));
+        assert!(fee_rate > 7.0 && fee_rate < 7.2);
+
+        // Fee rate above the maximum
+        assert!(check_broadcast_policy(&txn, &prevouts(120_000), &policy).is_err());
+
+        // Fee percent above the maximum
+        let txn = transaction(&[1_000]);
+        assert!(check_broadcast_policy(&txn, &prevouts(1_100), &policy).is_err());
+
+        // Dust output
+        let txn = transaction(&[99_000, 100]);
+        assert!(check_broadcast_policy(&txn, &prevouts(100_000), &policy).is_err());
+
+        // Outputs worth more than the inputs
+        let txn = transaction(&[100_001]);
+        assert!(check_broadcast_policy(&txn, &prevouts(100_000), &policy).is_err());
+
+        // The change does not count as sent, 1000 sats fee is 10% of the 10000 sats payment
+        let mut txn = transaction(&[10_000, 89_000]);
+        txn.output[0].script_pubkey = ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array([8; 20]));
+        assert!(check_broadcast_policy(&txn, &prevouts(100_000), &policy).is_err());
+
+        let policy = BroadcastPolicyConfig {
+            max_fee_percent: 10.0,
+            ..policy
+        };
+        assert!(check_broadcast_policy(&txn, &prevouts(100_000), &policy).is_ok());
+
+        // Non standard output script
+        txn.output[0].script_pubkey = ScriptBuf::from_bytes(vec![0x51]);
+        assert!(check_broadcast_policy(&txn, &prevouts(100_000), &policy).is_err());
+
+        // Output values overflowing in total
+        let txn = transaction(&[u64::MAX, 1]);
+        assert!(matches!(
+            check_broadcast_policy(&txn, &prevouts(100_000), &policy),
+            Err(BtcApiError::InvalidRawTransaction(_))
+        ));
+    }
+}
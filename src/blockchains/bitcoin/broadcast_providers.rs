@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use bitcoin::Txid;
+use bitcoincore_rpc::RpcApi;
+use reqwest::{Client, Url};
+
+use crate::{
+    blockchains::bitcoin::{broadcast_error::classify_reject_reason, utils::url_host},
+    btc_api_error::BtcApiError,
+    models::BroadcastErrorCode,
+};
+
+// An endpoint a signed transaction is relayed to
+#[derive(Debug, Clone)]
+pub enum BroadcastProvider {
+    // Esplora compatible REST API (blockstream.info, mempool.space, self hosted esplora)
+    Esplora(Url),
+    Bitcoind(Arc<bitcoincore_rpc::Client>),
+}
+
+impl BroadcastProvider {
+    // Only the host of the URL, which is shown in the API responses
+    pub fn name(&self) -> String {
+        match self {
+            BroadcastProvider::Esplora(url) => format!("esplora({})", url_host(url)),
+            BroadcastProvider::Bitcoind(_) => "bitcoind".to_string(),
+        }
+    }
+
+    // Returns the txid reported by the provider
+    pub async fn broadcast(
+        &self,
+        client: &Client,
+        signed_txn_hash: &str,
+    ) -> Result<Txid, BtcApiError> {
+        match self {
+            BroadcastProvider::Esplora(url) => {
+                let response = client
+                    .post(url.join("tx")?)
+                    .body(signed_txn_hash.to_string())
+                    .send()
+                    .await?;
+                let response_text = response.text().await?;
+
+                match response_text.trim().parse::<Txid>() {
+                    Ok(txid) => Ok(txid),
+                    Err(_) => Err(rejection(response_text)),
+                }
+            }
+            BroadcastProvider::Bitcoind(rpc_client) => {
+                let rpc_client = rpc_client.clone();
+                let signed_txn_hash = signed_txn_hash.to_string();
+
+                tokio::task::spawn_blocking(move || {
+                    rpc_client.send_raw_transaction(signed_txn_hash.as_str())
+                })
+                .await
+                .map_err(|err| BtcApiError::ExternalApiError(err.to_string()))?
+                .map_err(|err| rejection(err.to_string()))
+            }
+        }
+    }
+}
+
+fn rejection(reject_reason: String) -> BtcApiError {
+    match classify_reject_reason(&reject_reason) {
+        BroadcastErrorCode::Unknown => BtcApiError::InvalidBroadcastResponse(reject_reason),
+        code => BtcApiError::TransactionRejected(code, reject_reason),
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashSet;
+
+use bitcoin::{AddressType, Amount, OutPoint, TxOut};
+
+use crate::{
+    blockchains::bitcoin::{response_models::BlockstreamUtxo, utils::estimate_txn_vsize},
+    btc_api_error::BtcApiError,
+};
+
+// Caller preferences for the UTXOs spent by a transaction
+#[derive(Debug, Default)]
+pub struct CoinControl {
+    // Always spent, even when unconfirmed, before any other UTXO
+    pub include: Vec<OutPoint>,
+    // Never spent
+    pub exclude: HashSet<OutPoint>,
+}
+
+impl CoinControl {
+    // An outpoint can only be included once and can not be both included and excluded
+    pub fn parse(include_utxos: &[String], exclude_utxos: &[String]) -> Result<Self, BtcApiError> {
+        let include = parse_outpoints(include_utxos)?;
+        let exclude = parse_outpoints(exclude_utxos)?
+            .into_iter()
+            .collect::<HashSet<OutPoint>>();
+
+        let mut included = HashSet::new();
+        for outpoint in &include {
+            if !included.insert(outpoint) {
+                return Err(BtcApiError::InvalidCoinControl(format!(
+                    "{} is included more than once",
+                    outpoint
+                )));
+            }
+
+            if exclude.contains(outpoint) {
+                return Err(BtcApiError::InvalidCoinControl(format!(
+                    "{} is both included and excluded",
+                    outpoint
+                )));
+            }
+        }
+
+        Ok(Self { include, exclude })
+    }
+}
+
+pub fn parse_outpoints(outpoints: &[String]) -> Result<Vec<OutPoint>, BtcApiError> {
+    outpoints
+        .iter()
+        .map(|outpoint| {
+            outpoint
+                .parse::<OutPoint>()
+                .map_err(|err| BtcApiError::InvalidOutPoint(format!("{} ({})", outpoint, err)))
+        })
+        .collect()
+}
+
+// Values are reported by the provider, their sum is checked rather than trusted
+pub fn sum_utxo_values(utxos: &[BlockstreamUtxo]) -> Result<Amount, BtcApiError> {
+    utxos
+        .iter()
+        .map(|utxo| Amount::from_sat(utxo.value))
+        .try_fold(Amount::ZERO, Amount::checked_add)
+        .ok_or_else(|| {
+            BtcApiError::ExternalApiError("Sum of the UTXO values overflows".to_string())
+        })
+}
+
+// Selects the UTXOs to cover target_amount: the included UTXOs first, then the confirmed
+// UTXOs which are neither excluded nor locked, smallest first so the transaction is split up
+// as much as possible.
+pub fn select_utxos(
+    mut utxos: Vec<BlockstreamUtxo>,
+    target_amount: u64,
+    coin_control: &CoinControl,
+    locked: &HashSet<OutPoint>,
+) -> Result<Vec<BlockstreamUtxo>, BtcApiError> {
+    let mut selected_utxos = vec![];
+    let mut total_utxo_value: u64 = 0;
+
+    for outpoint in &coin_control.include {
+        if locked.contains(outpoint) {
+            return Err(BtcApiError::UtxoLocked(outpoint.to_string()));
+        }
+
+        let position = utxos
+            .iter()
+            .position(|utxo| utxo.get_outpoint().ok().as_ref() == Some(outpoint))
+            .ok_or_else(|| BtcApiError::UtxoNotFound(outpoint.to_string()))?;
+
+        let utxo = utxos.remove(position);
+        total_utxo_value = total_utxo_value.saturating_add(utxo.value);
+        selected_utxos.push(utxo);
+    }
+
+    utxos.sort_by_key(|utxo| utxo.value);
+
+    for utxo in utxos {
+        if total_utxo_value >= target_amount {
+            break;
+        }
+
+        let outpoint = utxo.get_outpoint()?;
+        if utxo.is_confirmed()
+            && !coin_control.exclude.contains(&outpoint)
+            && !locked.contains(&outpoint)
+        {
+            total_utxo_value = total_utxo_value.saturating_add(utxo.value);
+            selected_utxos.push(utxo);
+        }
+    }
+
+    if target_amount > total_utxo_value {
+        return Err(BtcApiError::InsufficientFunds(
+            target_amount - total_utxo_value,
+        ));
+    }
+
+    Ok(selected_utxos)
+}
+
+// Selects the UTXOs for amount plus the fee at fee_rate of the transaction spending them.
+// Spending more inputs raises the fee, so the selection is repeated until it covers its own
+// fee. Returns the selected UTXOs, the signed vsize and the fee.
+pub fn select_utxos_for_fee_rate(
+    utxos: &[BlockstreamUtxo],
+    amount: u64,
+    fee_rate: f64,
+    coin_control: &CoinControl,
+    locked: &HashSet<OutPoint>,
+    input_type: AddressType,
+    outputs: &[TxOut],
+) -> Result<(Vec<BlockstreamUtxo>, u64, u64), BtcApiError> {
+    let mut fee = 0;
+
+    loop {
+        let target_amount = amount.checked_add(fee).ok_or_else(|| {
+            BtcApiError::InvalidFee(format!("Amount {} plus fee {} overflows", amount, fee))
+        })?;
+        let selected_utxos = select_utxos(utxos.to_vec(), target_amount, coin_control, locked)?;
+        let vsize = estimate_txn_vsize(input_type, selected_utxos.len(), outputs)?;
+        let required_fee = (vsize as f64 * fee_rate).ceil() as u64;
+
+        if required_fee <= fee {
+            return Ok((selected_utxos, vsize, fee));
+        }
+
+        fee = required_fee;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utxos() -> Vec<BlockstreamUtxo> {
+        let json = r#"
+        [
+            {
+                "txid": "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2",
+                "vout": 0,
+                "status": { "confirmed": true, "block_height": 3659267 },
+                "value": 30000
+            },
+            {
+                "txid": "d6db69946d2eece44bcda9e6beb2e859ad627662b53a917679b9ea8e70e1d60f",
+                "vout": 0,
+                "status": { "confirmed": true, "block_height": 3659268 },
+                "value": 10000
+            },
+            {
+                "txid": "a2a9afba41ea32a4c04e8984e84593796de447ac7b8f6caed9265ef332b21223",
+                "vout": 1,
+                "status": { "confirmed": false },
+                "value": 50000
+            },
+            {
+                "txid": "69f8ab2bf2d82b3e5fd7626736d040d9c11d4ea3c31fb0c30bb0d72e8c5a6238",
+                "vout": 2,
+                "status": { "confirmed": true, "block_height": 3659269 },
+                "value": 20000
+            }
+        ]
+        "#;
+
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn txids(utxos: &[BlockstreamUtxo]) -> Vec<String> {
+        utxos
+            .iter()
+            .map(|utxo| utxo.txid[..4].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_select_smallest_confirmed_utxos_first() {
+        let selected =
+            select_utxos(utxos(), 25000, &CoinControl::default(), &HashSet::new()).unwrap();
+
+        assert_eq!(txids(&selected), vec!["d6db", "69f8"]);
+    }
+
+    #[test]
+    fn test_select_with_coin_control_and_locks() {
+        let coin_control = CoinControl::parse(
+            &["a2a9afba41ea32a4c04e8984e84593796de447ac7b8f6caed9265ef332b21223:1".to_string()],
+            &["d6db69946d2eece44bcda9e6beb2e859ad627662b53a917679b9ea8e70e1d60f:0".to_string()],
+        )
+        .unwrap();
+        let locked = parse_outpoints(&[
+            "69f8ab2bf2d82b3e5fd7626736d040d9c11d4ea3c31fb0c30bb0d72e8c5a6238:2".to_string(),
+        ])
+        .unwrap()
+        .into_iter()
+        .collect::<HashSet<OutPoint>>();
+
+        // Included unconfirmed UTXO goes first, excluded and locked UTXOs are skipped
+        let selected = select_utxos(utxos(), 60000, &coin_control, &locked).unwrap();
+        assert_eq!(txids(&selected), vec!["a2a9", "cf63"]);
+
+        // Not enough left once excluded and locked UTXOs are skipped
+        let insufficient = select_utxos(utxos(), 90000, &coin_control, &locked);
+        assert!(matches!(
+            insufficient,
+            Err(BtcApiError::InsufficientFunds(10000))
+        ));
+
+        // Included UTXOs can not be locked
+        let locked_include = CoinControl::parse(
+            &["69f8ab2bf2d82b3e5fd7626736d040d9c11d4ea3c31fb0c30bb0d72e8c5a6238:2".to_string()],
+            &[],
+        )
+        .unwrap();
+        assert!(matches!(
+            select_utxos(utxos(), 1000, &locked_include, &locked),
+            Err(BtcApiError::UtxoLocked(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_coin_control_conflicts() {
+        let outpoint =
+            "a2a9afba41ea32a4c04e8984e84593796de447ac7b8f6caed9265ef332b21223:1".to_string();
+
+        assert!(matches!(
+            CoinControl::parse(
+                std::slice::from_ref(&outpoint),
+                std::slice::from_ref(&outpoint)
+            ),
+            Err(BtcApiError::InvalidCoinControl(_))
+        ));
+        assert!(matches!(
+            CoinControl::parse(&[outpoint.clone(), outpoint.clone()], &[]),
+            Err(BtcApiError::InvalidCoinControl(_))
+        ));
+
+        // Excluding the same outpoint twice is harmless
+        assert!(CoinControl::parse(&[], &[outpoint.clone(), outpoint]).is_ok());
+    }
+
+    #[test]
+    fn test_select_utxos_for_fee_rate() {
+        use bitcoin::hashes::Hash;
+
+        let outputs = [
+            TxOut {
+                value: bitcoin::Amount::ZERO,
+                script_pubkey: bitcoin::ScriptBuf::new_p2wpkh(
+                    &bitcoin::WPubkeyHash::from_byte_array([1; 20]),
+                ),
+            },
+            TxOut {
+                value: bitcoin::Amount::ZERO,
+                script_pubkey: bitcoin::ScriptBuf::new_p2wpkh(
+                    &bitcoin::WPubkeyHash::from_byte_array([2; 20]),
+                ),
+            },
+        ];
+        let quote = |amount, fee_rate| {
+            select_utxos_for_fee_rate(
+                &utxos(),
+                amount,
+                fee_rate,
+                &CoinControl::default(),
+                &HashSet::new(),
+                AddressType::P2wpkh,
+                &outputs,
+            )
+        };
+
+        // A single input covers the amount and its fee
+        let (selected, vsize, fee) = quote(9000, 2.0).unwrap();
+        assert_eq!(txids(&selected), vec!["d6db"]);
+        assert_eq!(
+            vsize,
+            estimate_txn_vsize(AddressType::P2wpkh, 1, &outputs).unwrap()
+        );
+        assert_eq!(fee, vsize * 2);
+
+        // The fee pushes the selection to a second input, which is paid for too
+        let (selected, vsize, fee) = quote(9900, 2.0).unwrap();
+        assert_eq!(txids(&selected), vec!["d6db", "69f8"]);
+        assert_eq!(
+            vsize,
+            estimate_txn_vsize(AddressType::P2wpkh, 2, &outputs).unwrap()
+        );
+        assert_eq!(fee, vsize * 2);
+
+        assert!(matches!(
+            quote(60000, 2.0),
+            Err(BtcApiError::InsufficientFunds(_))
+        ));
+
+        // An amount which only overflows once the fee is added
+        let mut utxos = utxos();
+        utxos.truncate(1);
+        utxos[0].value = u64::MAX;
+        assert!(matches!(
+            select_utxos_for_fee_rate(
+                &utxos,
+                u64::MAX - 10,
+                2.0,
+                &CoinControl::default(),
+                &HashSet::new(),
+                AddressType::P2wpkh,
+                &outputs,
+            ),
+            Err(BtcApiError::InvalidFee(_))
+        ));
+    }
+}
@@ -0,0 +1,518 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bitcoincore_rpc::RpcApi;
+use futures::future::join_all;
+use reqwest::{Client, Url};
+use tracing::{debug, error};
+
+use crate::{
+    blockchains::bitcoin::{response_models::MempoolSpaceFees, utils::url_host},
+    btc_api_error::BtcApiError,
+    models::{
+        FeeConfidence, FeeFallback, NetworkFeeResponseData, TargetFeeRate, TargetFeeRateSource,
+    },
+};
+
+// Confirmation targets in blocks of the fastest, half hour, hour, economy and minimum fees
+const FEE_TARGETS: [u16; 5] = [1, 3, 6, 144, 1008];
+
+// Fee rates in sat/vB, ordered like FEE_TARGETS
+type FeeRates = [f64; 5];
+
+// An endpoint fee rates are estimated from
+#[derive(Debug, Clone)]
+pub enum FeeSource {
+    // mempool.space compatible REST API
+    MempoolSpace(Url),
+    // Esplora compatible REST API, /fee-estimates
+    Esplora(Url),
+    // estimatesmartfee of the bitcoind node
+    Bitcoind(Arc<bitcoincore_rpc::Client>),
+}
+
+impl FeeSource {
+    // Only the host of the URL, which is shown in the API responses
+    pub fn name(&self) -> String {
+        match self {
+            FeeSource::MempoolSpace(url) => format!("mempoolSpace({})", url_host(url)),
+            FeeSource::Esplora(url) => format!("esplora({})", url_host(url)),
+            FeeSource::Bitcoind(_) => "bitcoind".to_string(),
+        }
+    }
+
+    async fn get_fee_rates(&self, client: &Client) -> Result<FeeRates, BtcApiError> {
+        match self {
+            FeeSource::MempoolSpace(url) => {
+                let fees = client
+                    .get(url.join("v1/fees/recommended")?)
+                    .send()
+                    .await?
+                    .json::<MempoolSpaceFees>()
+                    .await?;
+
+                Ok([
+                    fees.fastest_fee,
+                    fees.half_hour_fee,
+                    fees.hour_fee,
+                    fees.economy_fee,
+                    fees.minimum_fee,
+                ])
+            }
+            FeeSource::Esplora(url) => {
+                let estimates = client
+                    .get(url.join("fee-estimates")?)
+                    .send()
+                    .await?
+                    .json::<HashMap<String, f64>>()
+                    .await?;
+
+                get_esplora_fee_rates(&estimates)
+            }
+            FeeSource::Bitcoind(rpc_client) => {
+                let rpc_client = rpc_client.clone();
+
+                tokio::task::spawn_blocking(move || {
+                    let mut fee_rates = [0.0; 5];
+                    for (fee_rate, target) in fee_rates.iter_mut().zip(FEE_TARGETS) {
+                        // BTC/kvB
+                        let estimate = rpc_client.estimate_smart_fee(target, None)?;
+                        *fee_rate = estimate
+                            .fee_rate
+                            .map(|fee_rate| fee_rate.to_sat() as f64 / 1000.0)
+                            .ok_or_else(|| {
+                                BtcApiError::ExternalApiError(format!(
+                                    "No estimatesmartfee estimate for {} blocks: {:?}",
+                                    target,
+                                    estimate.errors.unwrap_or_default()
+                                ))
+                            })?;
+                    }
+
+                    Ok(fee_rates)
+                })
+                .await
+                .map_err(|err| BtcApiError::ExternalApiError(err.to_string()))?
+            }
+        }
+    }
+
+    // Returns the fee rate for the target along with the target the source estimated it for
+    async fn get_target_fee_rate(
+        &self,
+        client: &Client,
+        target: u16,
+    ) -> Result<(u16, f64), BtcApiError> {
+        let estimates = match self {
+            FeeSource::MempoolSpace(_) => FEE_TARGETS
+                .into_iter()
+                .zip(self.get_fee_rates(client).await?)
+                .collect::<Vec<(u16, f64)>>(),
+            FeeSource::Esplora(url) => client
+                .get(url.join("fee-estimates")?)
+                .send()
+                .await?
+                .json::<HashMap<String, f64>>()
+                .await?
+                .into_iter()
+                .filter_map(|(target, fee_rate)| Some((target.parse::<u16>().ok()?, fee_rate)))
+                .collect(),
+            FeeSource::Bitcoind(rpc_client) => {
+                let rpc_client = rpc_client.clone();
+
+                let estimate = tokio::task::spawn_blocking(move || {
+                    rpc_client.estimate_smart_fee(target, None)
+                })
+                .await
+                .map_err(|err| BtcApiError::ExternalApiError(err.to_string()))??;
+
+                // BTC/kvB, bitcoind falls back to the closest target it has data for
+                let fee_rate = estimate.fee_rate.ok_or_else(|| {
+                    BtcApiError::ExternalApiError(format!(
+                        "No estimatesmartfee estimate for {} blocks: {:?}",
+                        target,
+                        estimate.errors.unwrap_or_default()
+                    ))
+                })?;
+                vec![(estimate.blocks as u16, fee_rate.to_sat() as f64 / 1000.0)]
+            }
+        };
+
+        get_fee_rate_for_target(&estimates, target)
+    }
+}
+
+// A target is served by the estimate of the largest target not above it, or the smallest one
+// when all are above it
+fn get_fee_rate_for_target(
+    estimates: &[(u16, f64)],
+    target: u16,
+) -> Result<(u16, f64), BtcApiError> {
+    estimates
+        .iter()
+        .filter(|(estimate_target, _)| *estimate_target <= target)
+        .max_by_key(|(estimate_target, _)| *estimate_target)
+        .or_else(|| {
+            estimates
+                .iter()
+                .min_by_key(|(estimate_target, _)| *estimate_target)
+        })
+        .copied()
+        .ok_or_else(|| {
+            BtcApiError::ExternalApiError(format!("No fee estimate for {} blocks", target))
+        })
+}
+
+// Esplora returns the fee rate per confirmation target (1-25, 144, 504, 1008)
+fn get_esplora_fee_rates(estimates: &HashMap<String, f64>) -> Result<FeeRates, BtcApiError> {
+    let estimates = estimates
+        .iter()
+        .filter_map(|(target, fee_rate)| Some((target.parse::<u16>().ok()?, *fee_rate)))
+        .collect::<Vec<(u16, f64)>>();
+
+    let mut fee_rates = [0.0; 5];
+    for (fee_rate, target) in fee_rates.iter_mut().zip(FEE_TARGETS) {
+        *fee_rate = get_fee_rate_for_target(&estimates, target)?.1;
+    }
+
+    Ok(fee_rates)
+}
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+
+    if values.len().is_multiple_of(2) {
+        Some((values[middle - 1] + values[middle]) / 2.0)
+    } else {
+        Some(values[middle])
+    }
+}
+
+// Drops the values more than max_deviation times above or below the median of all of them
+fn get_inliers(values: &[f64], max_deviation: f64) -> Vec<f64> {
+    let Some(all_median) = median(&mut values.to_vec()) else {
+        return vec![];
+    };
+
+    values
+        .iter()
+        .copied()
+        .filter(|value| {
+            *value <= all_median * max_deviation && *value * max_deviation >= all_median
+        })
+        .collect()
+}
+
+fn aggregate(values: &[f64], max_deviation: f64) -> Option<f64> {
+    median(&mut get_inliers(values, max_deviation))
+}
+
+// Network fee rates aggregated from several sources, cached for cache_ttl. When every source
+// fails the last estimate is returned, or the floor when there never was one.
+#[derive(Debug, Clone)]
+pub struct FeeEstimator {
+    sources: Vec<FeeSource>,
+    cache: Arc<Mutex<Option<(Instant, NetworkFeeResponseData)>>>,
+    target_cache: Arc<Mutex<HashMap<u16, (Instant, TargetFeeRate)>>>,
+    cache_ttl: Duration,
+    // In sat/vB
+    min_fee_rate: f64,
+    max_deviation: f64,
+}
+
+impl FeeEstimator {
+    pub fn new(
+        sources: Vec<FeeSource>,
+        cache_ttl: Duration,
+        min_fee_rate: f64,
+        max_deviation: f64,
+    ) -> Self {
+        Self {
+            sources,
+            cache: Arc::new(Mutex::new(None)),
+            target_cache: Arc::new(Mutex::new(HashMap::new())),
+            cache_ttl,
+            min_fee_rate,
+            max_deviation,
+        }
+    }
+
+    pub fn sources(&self) -> &[FeeSource] {
+        &self.sources
+    }
+
+    pub async fn get_network_fee(&self) -> NetworkFeeResponseData {
+        if let Some((fetched_at, network_fee)) =
+            self.cache.lock().expect("fee cache poisoned").as_ref()
+        {
+            if fetched_at.elapsed() < self.cache_ttl {
+                return network_fee.clone();
+            }
+        }
+
+        let client = Client::new();
+        let results = join_all(
+            self.sources
+                .iter()
+                .map(|source| async { (source.name(), source.get_fee_rates(&client).await) }),
+        )
+        .await;
+
+        let mut sources = vec![];
+        let mut source_fee_rates = vec![];
+        for (source, result) in results {
+            match result {
+                Ok(fee_rates) => {
+                    debug!("Fee rates from {}: {:?}", source, fee_rates);
+                    sources.push(source);
+                    source_fee_rates.push(fee_rates);
+                }
+                Err(err) => error!("Failed to get fee rates from {}: {}", source, err),
+            }
+        }
+
+        match self.aggregate(&source_fee_rates) {
+            Some(fee_rates) => {
+                let network_fee = self.to_network_fee(fee_rates, sources, None);
+                *self.cache.lock().expect("fee cache poisoned") =
+                    Some((Instant::now(), network_fee.clone()));
+
+                network_fee
+            }
+            None => match self.cache.lock().expect("fee cache poisoned").as_ref() {
+                Some((_, network_fee)) => NetworkFeeResponseData {
+                    fallback: Some(FeeFallback::LastKnown),
+                    ..network_fee.clone()
+                },
+                None => self.to_network_fee([0.0; 5], vec![], Some(FeeFallback::Floor)),
+            },
+        }
+    }
+
+    // Fee rate for confirmation within target_blocks. The confidence is high when at least two
+    // sources agree, medium with a single source and low when falling back.
+    pub async fn get_target_fee_rate(&self, target_blocks: u16) -> TargetFeeRate {
+        if let Some((fetched_at, target_fee_rate)) = self
+            .target_cache
+            .lock()
+            .expect("fee cache poisoned")
+            .get(&target_blocks)
+        {
+            if fetched_at.elapsed() < self.cache_ttl {
+                return target_fee_rate.clone();
+            }
+        }
+
+        let client = Client::new();
+        let results = join_all(self.sources.iter().map(|source| async {
+            (
+                source.name(),
+                source.get_target_fee_rate(&client, target_blocks).await,
+            )
+        }))
+        .await;
+
+        let mut sources = vec![];
+        for (source, result) in results {
+            match result {
+                Ok((source_target_blocks, fee_rate)) => sources.push(TargetFeeRateSource {
+                    source,
+                    target_blocks: source_target_blocks,
+                    fee_rate,
+                }),
+                Err(err) => error!(
+                    "Failed to get the fee rate for {} blocks from {}: {}",
+                    target_blocks, source, err
+                ),
+            }
+        }
+
+        let fee_rates = sources
+            .iter()
+            .map(|source| source.fee_rate)
+            .collect::<Vec<f64>>();
+        let mut inliers = get_inliers(&fee_rates, self.max_deviation);
+
+        let confidence = match inliers.len() {
+            0 => {
+                return match self
+                    .target_cache
+                    .lock()
+                    .expect("fee cache poisoned")
+                    .get(&target_blocks)
+                {
+                    Some((_, target_fee_rate)) => TargetFeeRate {
+                        confidence: FeeConfidence::Low,
+                        fallback: Some(FeeFallback::LastKnown),
+                        ..target_fee_rate.clone()
+                    },
+                    None => TargetFeeRate {
+                        target_blocks,
+                        fee_rate: self.min_fee_rate,
+                        confidence: FeeConfidence::Low,
+                        sources,
+                        fallback: Some(FeeFallback::Floor),
+                    },
+                }
+            }
+            1 => FeeConfidence::Medium,
+            _ => FeeConfidence::High,
+        };
+
+        let target_fee_rate = TargetFeeRate {
+            target_blocks,
+            fee_rate: median(&mut inliers)
+                .unwrap_or_default()
+                .max(self.min_fee_rate),
+            confidence,
+            sources,
+            fallback: None,
+        };
+        self.target_cache
+            .lock()
+            .expect("fee cache poisoned")
+            .insert(target_blocks, (Instant::now(), target_fee_rate.clone()));
+
+        target_fee_rate
+    }
+
+    fn aggregate(&self, source_fee_rates: &[FeeRates]) -> Option<FeeRates> {
+        let mut fee_rates = [0.0; 5];
+        for (i, fee_rate) in fee_rates.iter_mut().enumerate() {
+            let values = source_fee_rates
+                .iter()
+                .map(|fee_rates| fee_rates[i])
+                .collect::<Vec<f64>>();
+            *fee_rate = aggregate(&values, self.max_deviation)?;
+        }
+
+        Some(fee_rates)
+    }
+
+    // Applies the floor and keeps a longer target from costing more than a shorter one
+    fn to_network_fee(
+        &self,
+        mut fee_rates: FeeRates,
+        sources: Vec<String>,
+        fallback: Option<FeeFallback>,
+    ) -> NetworkFeeResponseData {
+        let mut previous = f64::MAX;
+        for fee_rate in fee_rates.iter_mut() {
+            *fee_rate = fee_rate
+                .max(self.min_fee_rate)
+                .min(previous.max(self.min_fee_rate));
+            previous = *fee_rate;
+        }
+
+        NetworkFeeResponseData {
+            fastest_fee: fee_rates[0],
+            half_hour_fee: fee_rates[1],
+            hour_fee: fee_rates[2],
+            economy_fee: fee_rates[3],
+            minimum_fee: fee_rates[4],
+            sources,
+            fallback,
+            target: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate() {
+        // The outlier is dropped before taking the median
+        assert_eq!(aggregate(&[10.0, 12.0, 100.0], 3.0), Some(11.0));
+        assert_eq!(aggregate(&[10.0, 12.0, 14.0], 3.0), Some(12.0));
+        assert_eq!(aggregate(&[5.0], 3.0), Some(5.0));
+        assert_eq!(aggregate(&[], 3.0), None);
+    }
+
+    #[test]
+    fn test_get_esplora_fee_rates() {
+        let estimates = HashMap::from([
+            ("1".to_string(), 20.5),
+            ("2".to_string(), 18.0),
+            ("3".to_string(), 15.0),
+            ("5".to_string(), 10.0),
+            ("144".to_string(), 2.0),
+            ("1008".to_string(), 1.1),
+        ]);
+
+        assert_eq!(
+            get_esplora_fee_rates(&estimates).unwrap(),
+            [20.5, 15.0, 10.0, 2.0, 1.1]
+        );
+        assert!(get_esplora_fee_rates(&HashMap::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_network_fee_fallback() {
+        let fee_estimator = FeeEstimator::new(vec![], Duration::ZERO, 1.0, 3.0);
+
+        // Nothing was ever fetched
+        let network_fee = fee_estimator.get_network_fee().await;
+        assert_eq!(network_fee.fallback, Some(FeeFallback::Floor));
+        assert_eq!(network_fee.fastest_fee, 1.0);
+        assert_eq!(network_fee.minimum_fee, 1.0);
+
+        // The floor and the ordering of the targets are applied to the aggregate
+        let fee_rates = fee_estimator
+            .aggregate(&[[20.0, 25.0, 6.0, 0.5, 0.1], [22.0, 23.0, 8.0, 0.7, 0.1]])
+            .unwrap();
+        let network_fee = fee_estimator.to_network_fee(fee_rates, vec!["test".to_string()], None);
+        assert_eq!(
+            [
+                network_fee.fastest_fee,
+                network_fee.half_hour_fee,
+                network_fee.hour_fee,
+                network_fee.economy_fee,
+                network_fee.minimum_fee
+            ],
+            [21.0, 21.0, 7.0, 1.0, 1.0]
+        );
+
+        // The last estimate is returned once the sources fail
+        *fee_estimator.cache.lock().unwrap() = Some((Instant::now(), network_fee));
+        let network_fee = fee_estimator.get_network_fee().await;
+        assert_eq!(network_fee.fallback, Some(FeeFallback::LastKnown));
+        assert_eq!(network_fee.fastest_fee, 21.0);
+        assert_eq!(network_fee.sources, vec!["test".to_string()]);
+    }
+
+    #[test]
+    fn test_get_fee_rate_for_target() {
+        let estimates = [(2, 18.0), (6, 10.0), (144, 2.0)];
+
+        assert_eq!(get_fee_rate_for_target(&estimates, 6).unwrap(), (6, 10.0));
+        assert_eq!(get_fee_rate_for_target(&estimates, 100).unwrap(), (6, 10.0));
+        assert_eq!(
+            get_fee_rate_for_target(&estimates, 2000).unwrap(),
+            (144, 2.0)
+        );
+        // Below every target the fastest estimate is used
+        assert_eq!(get_fee_rate_for_target(&estimates, 1).unwrap(), (2, 18.0));
+        assert!(get_fee_rate_for_target(&[], 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_target_fee_rate_fallback() {
+        let fee_estimator = FeeEstimator::new(vec![], Duration::ZERO, 1.5, 3.0);
+
+        let target_fee_rate = fee_estimator.get_target_fee_rate(12).await;
+        assert_eq!(target_fee_rate.target_blocks, 12);
+        assert_eq!(target_fee_rate.fee_rate, 1.5);
+        assert_eq!(target_fee_rate.confidence, FeeConfidence::Low);
+        assert_eq!(target_fee_rate.fallback, Some(FeeFallback::Floor));
+    }
+}
@@ -0,0 +1,452 @@
+use bitcoin::{
+    absolute::LockTime,
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    blockdata::{
+        opcodes::all::{OP_PUSHBYTES_0, OP_RETURN},
+        script::Builder,
+    },
+    consensus::encode::{deserialize, serialize},
+    ecdsa,
+    hashes::{sha256, Hash, HashEngine},
+    key::{Keypair, Secp256k1, TapTweak, XOnlyPublicKey},
+    secp256k1::{
+        ecdsa::{RecoverableSignature, RecoveryId},
+        schnorr, Message, SecretKey,
+    },
+    sighash::{Prevouts, SighashCache},
+    sign_message::signed_msg_hash,
+    transaction::Version,
+    Address, AddressType, Amount, CompressedPublicKey, EcdsaSighashType, Network, OutPoint,
+    PublicKey, ScriptBuf, Sequence, TapSighashType, Transaction, TxIn, TxOut, Witness,
+};
+
+use crate::{btc_api_error::BtcApiError, models::MessageSignatureFormat};
+
+// BIP137 header bytes are 27 + recovery id + the offset of the signing address type
+const BIP137_HEADER_BASE: u8 = 27;
+const BIP137_P2PKH_COMPRESSED_OFFSET: u8 = 4;
+const BIP137_P2SH_P2WPKH_OFFSET: u8 = 8;
+const BIP137_P2WPKH_OFFSET: u8 = 12;
+const BIP322_TAG: &[u8] = b"BIP0322-signed-message";
+
+// BIP137 for the key hash addresses, BIP322 for taproot which BIP137 does not cover
+pub fn default_signature_format(address: &Address) -> MessageSignatureFormat {
+    match address.address_type() {
+        Some(AddressType::P2tr) => MessageSignatureFormat::Bip322,
+        _ => MessageSignatureFormat::Bip137,
+    }
+}
+
+// Signs with the key of the address, which has to be derived from secret_key. Returns the base64
+// encoded signature.
+pub fn sign_message(
+    secret_key: &SecretKey,
+    address: &Address,
+    message: &str,
+    format: &MessageSignatureFormat,
+) -> Result<String, BtcApiError> {
+    let secp = Secp256k1::new();
+    let public_key = CompressedPublicKey(secret_key.public_key(&secp));
+
+    match format {
+        MessageSignatureFormat::Bip137 => {
+            let offset = match address.address_type() {
+                Some(AddressType::P2pkh) => BIP137_P2PKH_COMPRESSED_OFFSET,
+                Some(AddressType::P2sh) => BIP137_P2SH_P2WPKH_OFFSET,
+                Some(AddressType::P2wpkh) => BIP137_P2WPKH_OFFSET,
+                _ => return Err(unsupported(address, format)),
+            };
+
+            let msg = Message::from_digest(signed_msg_hash(message).to_byte_array());
+            let (recovery_id, signature) = secp
+                .sign_ecdsa_recoverable(&msg, secret_key)
+                .serialize_compact();
+
+            let mut bytes = vec![BIP137_HEADER_BASE + offset + recovery_id.to_i32() as u8];
+            bytes.extend_from_slice(&signature);
+
+            Ok(BASE64.encode(bytes))
+        }
+        MessageSignatureFormat::Bip322 => {
+            let (to_spend, mut to_sign) = bip322_transactions(address, message);
+            let mut sighasher = SighashCache::new(&mut to_sign);
+
+            let witness = match address.address_type() {
+                Some(AddressType::P2wpkh) => {
+                    let sighash = sighasher
+                        .p2wpkh_signature_hash(
+                            0,
+                            &address.script_pubkey(),
+                            Amount::ZERO,
+                            EcdsaSighashType::All,
+                        )
+                        .map_err(|err| BtcApiError::MessageSigningError(err.to_string()))?;
+                    let signature = ecdsa::Signature {
+                        signature: secp.sign_ecdsa(&Message::from(sighash), secret_key),
+                        sighash_type: EcdsaSighashType::All,
+                    };
+
+                    Witness::p2wpkh(&signature, &public_key.0)
+                }
+                Some(AddressType::P2tr) => {
+                    let sighash = sighasher
+                        .taproot_key_spend_signature_hash(
+                            0,
+                            &Prevouts::All(&to_spend.output),
+                            TapSighashType::Default,
+                        )
+                        .map_err(|err| BtcApiError::MessageSigningError(err.to_string()))?;
+                    let keypair = Keypair::from_secret_key(&secp, secret_key)
+                        .tap_tweak(&secp, None)
+                        .to_inner();
+                    let signature =
+                        secp.sign_schnorr_no_aux_rand(&Message::from(sighash), &keypair);
+
+                    Witness::from_slice(&[signature.as_ref()])
+                }
+                _ => return Err(unsupported(address, format)),
+            };
+
+            Ok(BASE64.encode(serialize(&witness)))
+        }
+    }
+}
+
+// Verifies a base64 encoded BIP137 or BIP322 simple signature, the format is detected from the
+// signature. Invalid signatures are Ok(false), only undecodable ones are errors.
+pub fn verify_message(
+    address: &Address,
+    message: &str,
+    signature: &str,
+    network: Network,
+) -> Result<(bool, MessageSignatureFormat), BtcApiError> {
+    let bytes = BASE64
+        .decode(signature.trim())
+        .map_err(|err| BtcApiError::InvalidSignature(format!("Invalid base64: {}", err)))?;
+
+    // A BIP322 simple signature starts with the witness item count, never 27 or more
+    match bytes.first() {
+        Some(header) if bytes.len() == 65 && (27..=42).contains(header) => Ok((
+            verify_bip137(address, message, &bytes, network)?,
+            MessageSignatureFormat::Bip137,
+        )),
+        _ => Ok((
+            verify_bip322(address, message, &bytes)?,
+            MessageSignatureFormat::Bip322,
+        )),
+    }
+}
+
+fn verify_bip137(
+    address: &Address,
+    message: &str,
+    bytes: &[u8],
+    network: Network,
+) -> Result<bool, BtcApiError> {
+    let header = bytes[0] - BIP137_HEADER_BASE;
+    let recovery_id = RecoveryId::from_i32((header % 4) as i32)
+        .map_err(|err| BtcApiError::InvalidSignature(err.to_string()))?;
+    let signature = RecoverableSignature::from_compact(&bytes[1..], recovery_id)
+        .map_err(|err| BtcApiError::InvalidSignature(err.to_string()))?;
+
+    let msg = Message::from_digest(signed_msg_hash(message).to_byte_array());
+    let Ok(public_key) = Secp256k1::verification_only().recover_ecdsa(&msg, &signature) else {
+        return Ok(false);
+    };
+
+    // Many wallets use the compressed P2PKH header for segwit addresses too, so the address
+    // type of the compressed key is taken from the address itself
+    let recovered_address = match header {
+        0..=3 => Address::p2pkh(PublicKey::new_uncompressed(public_key), network),
+        _ => {
+            let public_key = CompressedPublicKey(public_key);
+            match address.address_type() {
+                Some(AddressType::P2pkh) => Address::p2pkh(public_key, network),
+                Some(AddressType::P2sh) => Address::p2shwpkh(&public_key, network),
+                Some(AddressType::P2wpkh) => Address::p2wpkh(&public_key, network),
+                _ => return Ok(false),
+            }
+        }
+    };
+
+    Ok(recovered_address == *address)
+}
+
+fn verify_bip322(address: &Address, message: &str, bytes: &[u8]) -> Result<bool, BtcApiError> {
+    let witness = deserialize::<Witness>(bytes)
+        .map_err(|err| BtcApiError::InvalidSignature(format!("Invalid BIP322 witness: {}", err)))?;
+
+    let (to_spend, to_sign) = bip322_transactions(address, message);
+    let mut sighasher = SighashCache::new(&to_sign);
+    let secp = Secp256k1::verification_only();
+
+    match address.address_type() {
+        Some(AddressType::P2wpkh) => {
+            let (Some(signature), Some(public_key), 2) =
+                (witness.nth(0), witness.nth(1), witness.len())
+            else {
+                return Ok(false);
+            };
+
+            let (Ok(signature), Ok(public_key)) = (
+                ecdsa::Signature::from_slice(signature),
+                CompressedPublicKey::from_slice(public_key),
+            ) else {
+                return Ok(false);
+            };
+
+            if address.script_pubkey() != ScriptBuf::new_p2wpkh(&public_key.wpubkey_hash()) {
+                return Ok(false);
+            }
+
+            let sighash = sighasher
+                .p2wpkh_signature_hash(
+                    0,
+                    &address.script_pubkey(),
+                    Amount::ZERO,
+                    signature.sighash_type,
+                )
+                .map_err(|err| BtcApiError::InvalidSignature(err.to_string()))?;
+
+            Ok(secp
+                .verify_ecdsa(&Message::from(sighash), &signature.signature, &public_key.0)
+                .is_ok())
+        }
+        Some(AddressType::P2tr) => {
+            let (Some(signature), 1) = (witness.nth(0), witness.len()) else {
+                return Ok(false);
+            };
+
+            let sighash_type = match signature.len() {
+                64 => TapSighashType::Default,
+                65 => match TapSighashType::from_consensus_u8(signature[64]) {
+                    Ok(sighash_type) => sighash_type,
+                    Err(_) => return Ok(false),
+                },
+                _ => return Ok(false),
+            };
+
+            let (Ok(signature), Some(output_key)) = (
+                schnorr::Signature::from_slice(&signature[..64]),
+                address.witness_program().and_then(|program| {
+                    XOnlyPublicKey::from_slice(program.program().as_bytes()).ok()
+                }),
+            ) else {
+                return Ok(false);
+            };
+
+            let sighash = sighasher
+                .taproot_key_spend_signature_hash(0, &Prevouts::All(&to_spend.output), sighash_type)
+                .map_err(|err| BtcApiError::InvalidSignature(err.to_string()))?;
+
+            Ok(secp
+                .verify_schnorr(&signature, &Message::from(sighash), &output_key)
+                .is_ok())
+        }
+        _ => Err(unsupported(address, &MessageSignatureFormat::Bip322)),
+    }
+}
+
+// The virtual to_spend and to_sign transactions of BIP322, to_sign has no witness yet
+fn bip322_transactions(address: &Address, message: &str) -> (Transaction, Transaction) {
+    let message_hash = bip322_message_hash(message);
+
+    let to_spend = Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: Builder::new()
+                .push_opcode(OP_PUSHBYTES_0)
+                .push_slice(message_hash.to_byte_array())
+                .into_script(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: address.script_pubkey(),
+        }],
+    };
+
+    let to_sign = Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(to_spend.compute_txid(), 0),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+        }],
+    };
+
+    (to_spend, to_sign)
+}
+
+// Tagged hash of the message: sha256(sha256(tag) || sha256(tag) || message)
+fn bip322_message_hash(message: &str) -> sha256::Hash {
+    let tag_hash = sha256::Hash::hash(BIP322_TAG);
+
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag_hash.as_ref());
+    engine.input(tag_hash.as_ref());
+    engine.input(message.as_bytes());
+
+    sha256::Hash::from_engine(engine)
+}
+
+fn unsupported(address: &Address, format: &MessageSignatureFormat) -> BtcApiError {
+    BtcApiError::MessageSigningError(format!(
+        "{:?} signatures are not supported for {} addresses",
+        format,
+        address
+            .address_type()
+            .map(|address_type| address_type.to_string())
+            .unwrap_or("unknown".to_string())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::PrivateKey;
+
+    use super::*;
+
+    // Test vector of BIP322
+    const BIP322_WIF: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+    const BIP322_ADDRESS: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+
+    fn address(address: &str) -> Address {
+        Address::from_str(address)
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bip322_message_hash() {
+        assert_eq!(
+            bip322_message_hash("").to_string(),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+        assert_eq!(
+            bip322_message_hash("Hello World").to_string(),
+            "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify_bip322() {
+        let secret_key = PrivateKey::from_wif(BIP322_WIF).unwrap().inner;
+        let address = address(BIP322_ADDRESS);
+
+        // Signature from the BIP322 test vectors
+        let signature = "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+        assert_eq!(
+            verify_message(&address, "Hello World", signature, Network::Bitcoin).unwrap(),
+            (true, MessageSignatureFormat::Bip322)
+        );
+
+        let signature = sign_message(
+            &secret_key,
+            &address,
+            "Hello World",
+            &MessageSignatureFormat::Bip322,
+        )
+        .unwrap();
+        assert_eq!(
+            verify_message(&address, "Hello World", &signature, Network::Bitcoin).unwrap(),
+            (true, MessageSignatureFormat::Bip322)
+        );
+        assert_eq!(
+            verify_message(&address, "Hello World!", &signature, Network::Bitcoin).unwrap(),
+            (false, MessageSignatureFormat::Bip322)
+        );
+
+        // Taproot key path signature of the same key
+        let secp = Secp256k1::new();
+        let (internal_key, _) = secret_key.public_key(&secp).x_only_public_key();
+        let taproot_address = Address::p2tr(&secp, internal_key, None, Network::Bitcoin);
+
+        let signature = sign_message(
+            &secret_key,
+            &taproot_address,
+            "Hello World",
+            &MessageSignatureFormat::Bip322,
+        )
+        .unwrap();
+        assert!(
+            verify_message(
+                &taproot_address,
+                "Hello World",
+                &signature,
+                Network::Bitcoin
+            )
+            .unwrap()
+            .0
+        );
+        assert!(
+            !verify_message(&taproot_address, "Hello", &signature, Network::Bitcoin)
+                .unwrap()
+                .0
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify_bip137() {
+        let secret_key = PrivateKey::from_wif(BIP322_WIF).unwrap().inner;
+        let public_key = CompressedPublicKey(secret_key.public_key(&Secp256k1::new()));
+
+        for address in [
+            Address::p2pkh(public_key, Network::Bitcoin),
+            Address::p2shwpkh(&public_key, Network::Bitcoin),
+            Address::p2wpkh(&public_key, Network::Bitcoin),
+        ] {
+            let signature = sign_message(
+                &secret_key,
+                &address,
+                "Hello World",
+                &MessageSignatureFormat::Bip137,
+            )
+            .unwrap();
+
+            assert_eq!(
+                verify_message(&address, "Hello World", &signature, Network::Bitcoin).unwrap(),
+                (true, MessageSignatureFormat::Bip137)
+            );
+            assert!(
+                !verify_message(&address, "Hello", &signature, Network::Bitcoin)
+                    .unwrap()
+                    .0
+            );
+        }
+
+        // Signed by another key
+        let other_address = Address::p2wpkh(
+            &CompressedPublicKey(
+                SecretKey::from_slice(&[1; 32])
+                    .unwrap()
+                    .public_key(&Secp256k1::new()),
+            ),
+            Network::Bitcoin,
+        );
+        let signature = sign_message(
+            &secret_key,
+            &address(BIP322_ADDRESS),
+            "Hello World",
+            &MessageSignatureFormat::Bip137,
+        )
+        .unwrap();
+        assert!(
+            !verify_message(&other_address, "Hello World", &signature, Network::Bitcoin)
+                .unwrap()
+                .0
+        );
+    }
+}
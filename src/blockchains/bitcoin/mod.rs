@@ -1,26 +1,84 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
 
 use bitcoin::{
-    absolute::LockTime, consensus::encode::serialize_hex, key::Secp256k1, secp256k1::Message,
-    sighash::SighashCache, transaction::Version, Address, Amount, EcdsaSighashType, Network,
-    OutPoint, Script, ScriptBuf, TxIn, TxOut, Txid, Witness,
+    absolute::LockTime,
+    consensus::encode::{deserialize_hex, serialize_hex},
+    key::Secp256k1,
+    secp256k1::Message,
+    sighash::SighashCache,
+    transaction::Version,
+    Address, AddressType, Amount, CompressedPublicKey, EcdsaSighashType, Network, OutPoint, Script,
+    ScriptBuf, TxIn, TxOut, Txid, Witness,
 };
 
 use bitcoin::blockdata::transaction::Transaction;
+use bitcoincore_rpc::{json::BlockStatsFields, Auth, RpcApi};
+use broadcast_error::classify_reject_reason;
+use broadcast_policy::check_broadcast_policy;
+use broadcast_providers::BroadcastProvider;
+use coin_selection::{
+    parse_outpoints, select_utxos, select_utxos_for_fee_rate, sum_utxo_values, CoinControl,
+};
+use fee_estimator::{FeeEstimator, FeeSource};
+use futures::{future::join_all, stream, StreamExt, TryStreamExt};
+use message_signing::{default_signature_format, sign_message, verify_message};
+use payment_uri::{create_payment_uri, parse_payment_uri};
+use price_feed::{to_fiat, FilePriceFeed, HttpPriceFeed, PriceFeed};
 use regex::Regex;
 use reqwest::{Client, Url};
-use response_models::{BlockchaincomResponse, BlockstreamUtxo, BlockstreamWalletBalance};
-use tracing::{debug, error, info};
-use utils::{is_valid_bitcoin_address, senders_keys};
+use response_models::{
+    BitcoindSubmitPackageResult, BlockchaincomResponse, BlockstreamBlock, BlockstreamMempool,
+    BlockstreamOutspend, BlockstreamTxn, BlockstreamUtxo, BlockstreamWalletBalance, Status,
+};
+use time_locks::TimeLocks;
+use tracing::{debug, error, info, warn};
+use transaction_decoder::decode_transaction;
+use utils::{
+    estimate_txn_vsize, get_address_info, is_valid_bitcoin_address, op_return_output, senders_keys,
+    url_host,
+};
+use utxo_locks::UtxoLocks;
+mod broadcast_error;
+mod broadcast_policy;
+mod broadcast_providers;
+mod coin_selection;
+mod fee_estimator;
+mod message_signing;
+pub(crate) mod payment_uri;
+mod price_feed;
 pub(crate) mod response_models;
+mod time_locks;
+mod transaction_decoder;
+mod utxo_locks;
 
 use crate::models::{
-    BroadcastTransactionParams, TransactionData, WalletBalanceResponse, WalletBalanceResponseData,
+    AddressInfoResponse, AddressTransaction, AddressTransactionsResponse,
+    AddressTransactionsResponseData, AddressUtxo, AddressUtxosParams, AddressUtxosResponse,
+    AddressUtxosResponseData, AddressWalletBalance, BlockResponse, BlockResponseData,
+    BlockTxidsResponse, BlockTxidsResponseData, BroadcastErrorCode, BroadcastTransactionParams,
+    ConsolidateTransactionParams, ConsolidateTransactionResponse,
+    ConsolidateTransactionResponseData, CreatePaymentUriParams, DecodeTransactionParams,
+    DecodeTransactionResponse, DecodeTransactionResponseData, EstimateTransactionFeeParams,
+    EstimateTransactionFeeResponse, EstimateTransactionFeeResponseData, FeeHistogramBucket,
+    FiatAddressTransactionValue, FiatBalance, FiatPrice, FiatTransactionValue, MempoolResponse,
+    MempoolResponseData, NetworkFeeParams, PackageTxnResult, ParsePaymentUriParams, PaymentUriData,
+    PaymentUriResponse, ProviderBroadcastResult, ReleaseUtxosParams, ReleaseUtxosResponse,
+    ReleaseUtxosResponseData, SignMessageParams, SignMessageResponse, SignMessageResponseData,
+    SubmitPackageParams, SubmitPackageResponse, SubmitPackageResponseData, TestTransactionParams,
+    TestTransactionResponse, TestTransactionResponseData, TimeLockInfo, TransactionData,
+    TransactionFeeQuote, TransactionHashValidation, UtxoState, ValidateTransactionHashesParams,
+    ValidateTransactionHashesResponse, ValidateTransactionHashesResponseData, VerifyMessageParams,
+    VerifyMessageResponse, VerifyMessageResponseData, WalletBalanceResponse,
+    WalletBalanceResponseData, WalletBalancesParams, WalletBalancesResponse,
+    WalletBalancesResponseData,
 };
 use crate::{
     btc_api_error::BtcApiError,
     chain::Chain,
-    config::ChainVariant,
+    config::{
+        BatchRequestsConfig, BitcoindConfig, BroadcastPolicyConfig, BroadcastProviderConfig,
+        ChainVariant, FeeEstimationConfig, FeeSourceConfig, PriceFeedConfig,
+    },
     models::{
         BroadcastTransactionResponse, BroadcastTransactionResponseData, CreateTransactionParams,
         CreateTransactionResponse, CreateTransactionResponseData, NetworkFeeResponse,
@@ -30,8 +88,9 @@ use crate::{
 };
 mod utils;
 
-// Mempool API for network fee
-const MEMPOOL_API_NETWORK_FEE_URL: &str = "https://mempool.space/api/v1/fees/recommended";
+// Mempool APIs for network fee
+const MEMPOOL_SPACE_MAINNET_API_URL: &str = "https://mempool.space/api/";
+const MEMPOOL_SPACE_TESTNET_API_URL: &str = "https://mempool.space/testnet/api/";
 // Blockchain API for raw transaction, always mainnet
 const BLOCKCHAIN_API_RAW_TRANSACTION_URL: &str = "https://blockchain.info/rawtx/";
 // Bitcoin txid regex
@@ -40,6 +99,20 @@ const BITCOIN_TXID_REGEX: &str = r"^[a-fA-F0-9]{64}$";
 const BLOCKSTREAM_TESTNET_EXPLORER_URL: &str = "https://blockstream.info/testnet/";
 // Blockstream Mainnet Explorer URL
 const BLOCKSTREAM_MAINNET_EXPLORER_URL: &str = "https://blockstream.info/";
+// Number of confirmed transactions returned by blockstream per address history page
+const BLOCKSTREAM_CHAIN_TXNS_PAGE_SIZE: usize = 25;
+// Default time for which the UTXOs used by a created transaction stay reserved
+const DEFAULT_UTXO_LOCK_TTL: Duration = Duration::from_secs(600);
+// Maximum number of transactions accepted by bitcoind submitpackage
+const MAX_PACKAGE_COUNT: usize = 25;
+
+// UTXOs locked for a created transaction, their outpoints, the change and the time lock info
+type LockedTransactionUtxos = (
+    Vec<BlockstreamUtxo>,
+    Vec<OutPoint>,
+    Amount,
+    Option<TimeLockInfo>,
+);
 
 #[derive(Debug, Clone)]
 pub struct Bitcoin {
@@ -48,46 +121,122 @@ pub struct Bitcoin {
     pub bitcoin_txid_regex: Regex,
     pub explorer_url: Url,
     pub sign_txn: bool,
+    pub utxo_locks: UtxoLocks,
+    pub broadcast_policy: BroadcastPolicyConfig,
+    pub bitcoind: Option<Arc<bitcoincore_rpc::Client>>,
+    pub broadcast_providers: Vec<BroadcastProvider>,
+    pub fee_estimator: FeeEstimator,
+    pub price_feed: Arc<dyn PriceFeed>,
+    pub batch_requests: BatchRequestsConfig,
 }
 
 #[async_trait::async_trait]
 #[mockall::automock]
 impl Chain for Bitcoin {
-    async fn get_network_fee(&self) -> NetworkFeeResponse {
-        {
-            let mut result = NetworkFeeResponse {
-                is_error: true,
-                data: None,
-                error_msg: None,
-            };
+    async fn get_network_fee(&self, params: NetworkFeeParams) -> NetworkFeeResponse {
+        let mut result = NetworkFeeResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
 
-            match reqwest::get(MEMPOOL_API_NETWORK_FEE_URL).await {
-                Ok(response) => match response.json().await {
-                    Ok(value) => match serde_json::from_value::<NetworkFeeResponseData>(value) {
-                        Ok(network_fee_response) => {
-                            result.is_error = false;
-                            result.data = Some(network_fee_response);
-                        }
-                        Err(err) => {
-                            result.error_msg = Some(err.to_string());
-                        }
-                    },
-                    Err(err) => {
-                        result.error_msg = Some(err.to_string());
-                    }
-                },
-                Err(err) => {
-                    result.error_msg = Some(err.to_string());
-                }
+        match self.get_network_fee_for_target(params).await {
+            Ok(network_fee) => {
+                result.is_error = false;
+                result.data = Some(network_fee);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
             }
+        }
 
-            result
+        result
+    }
+
+    async fn get_mempool(&self) -> MempoolResponse {
+        let mut result = MempoolResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.get_mempool().await {
+            Ok(mempool) => {
+                result.is_error = false;
+                result.data = Some(mempool);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn get_block_tip(&self) -> BlockResponse {
+        let mut result = BlockResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.get_block_tip().await {
+            Ok(block) => {
+                result.is_error = false;
+                result.data = Some(block);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn get_block(&self, hash_or_height: String) -> BlockResponse {
+        let mut result = BlockResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.get_block(hash_or_height).await {
+            Ok(block) => {
+                result.is_error = false;
+                result.data = Some(block);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn get_block_txids(&self, hash: String) -> BlockTxidsResponse {
+        let mut result = BlockTxidsResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.get_block_txids(hash).await {
+            Ok(block_txids) => {
+                result.is_error = false;
+                result.data = Some(block_txids);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
         }
+
+        result
     }
 
     async fn validate_transaction_hash(
         &self,
         transaction_hash: String,
+        currency: Option<String>,
     ) -> ValidateTransactionHashResponse {
         {
             let mut result = ValidateTransactionHashResponse {
@@ -95,7 +244,9 @@ impl Chain for Bitcoin {
                 data: None,
                 error_msg: None,
             };
-            let get_raw_txn_response = self.get_raw_transaction(transaction_hash).await;
+            let get_raw_txn_response = self
+                .validate_transaction_hash(transaction_hash, currency)
+                .await;
 
             match get_raw_txn_response {
                 Ok(validate_txn_data) => {
@@ -112,6 +263,29 @@ impl Chain for Bitcoin {
         }
     }
 
+    async fn validate_transaction_hashes(
+        &self,
+        params: ValidateTransactionHashesParams,
+    ) -> ValidateTransactionHashesResponse {
+        let mut result = ValidateTransactionHashesResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.validate_transaction_hashes(params).await {
+            Ok(transactions) => {
+                result.is_error = false;
+                result.data = Some(transactions);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
     async fn create_transaction(
         &self,
         transaction_params: CreateTransactionParams,
@@ -123,24 +297,66 @@ impl Chain for Bitcoin {
         };
 
         match self.create_transaction(transaction_params).await {
-            Ok((transaction, used_utxos)) => {
+            Ok(created_transaction) => {
                 if self.sign_txn {
                     let _signed_txn_hash = self
-                        .sign_transaction(transaction.clone(), used_utxos.clone())
+                        .sign_transaction(
+                            created_transaction.unsigned_raw_txn.clone(),
+                            created_transaction.used_utxos.clone(),
+                        )
                         .await;
                 }
 
                 // self.broadcast_transaction(signed_txn_hash).await.unwrap();
 
-                // Calculate weight & vBytes
-                let vbytes = (u64::from(transaction.weight()) + 3) / 4;
+                result.is_error = false;
+                result.data = Some(created_transaction);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn estimate_transaction_fee(
+        &self,
+        transaction: EstimateTransactionFeeParams,
+    ) -> EstimateTransactionFeeResponse {
+        let mut result = EstimateTransactionFeeResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
 
+        match self.estimate_transaction_fee(transaction).await {
+            Ok(fee_quotes) => {
                 result.is_error = false;
-                result.data = Some(CreateTransactionResponseData {
-                    unsigned_raw_txn: transaction,
-                    used_utxos,
-                    txn_size: vbytes,
-                });
+                result.data = Some(fee_quotes);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn consolidate_transaction(
+        &self,
+        consolidation: ConsolidateTransactionParams,
+    ) -> ConsolidateTransactionResponse {
+        let mut result = ConsolidateTransactionResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.consolidate_transaction(consolidation).await {
+            Ok(consolidation) => {
+                result.is_error = false;
+                result.data = Some(consolidation);
             }
             Err(err) => {
                 result.error_msg = Some(err.to_string());
@@ -158,14 +374,19 @@ impl Chain for Bitcoin {
             is_error: false,
             data: None,
             error_msg: None,
+            error_code: None,
         };
 
-        match self.broadcast_transaction(transaction.signed_raw_txn).await {
+        match self
+            .broadcast_transaction(transaction.signed_raw_txn, transaction.force)
+            .await
+        {
             Ok(broadcase_api_response) => {
                 result.data = Some(broadcase_api_response);
             }
             Err(err) => {
                 result.is_error = true;
+                result.error_code = err.broadcast_error_code();
                 result.error_msg = Some(err.to_string());
             }
         }
@@ -173,17 +394,20 @@ impl Chain for Bitcoin {
         result
     }
 
-    async fn get_wallet_balance(&self, address: String) -> WalletBalanceResponse {
-        let mut result = WalletBalanceResponse {
+    async fn decode_transaction(
+        &self,
+        transaction: DecodeTransactionParams,
+    ) -> DecodeTransactionResponse {
+        let mut result = DecodeTransactionResponse {
             is_error: true,
             data: None,
             error_msg: None,
         };
 
-        match self.get_wallet_balance(address).await {
-            Ok(wallet_balance) => {
+        match self.decode_transaction(transaction.raw_txn).await {
+            Ok(decoded_transaction) => {
                 result.is_error = false;
-                result.data = Some(wallet_balance);
+                result.data = Some(decoded_transaction);
             }
             Err(err) => {
                 result.error_msg = Some(err.to_string());
@@ -192,87 +416,808 @@ impl Chain for Bitcoin {
 
         result
     }
-}
 
-impl Bitcoin {
-    pub fn new(rpc_url: &str, variant: &ChainVariant, sign_txn: bool) -> Result<Self, BtcApiError> {
-        let (network, explorer_url) = match variant {
-            ChainVariant::Mainnet => (Network::Bitcoin, BLOCKSTREAM_MAINNET_EXPLORER_URL),
-            ChainVariant::Testnet => (Network::Testnet, BLOCKSTREAM_TESTNET_EXPLORER_URL),
+    async fn submit_package(&self, package: SubmitPackageParams) -> SubmitPackageResponse {
+        let mut result = SubmitPackageResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
         };
 
-        info!(
-            "Creating Bitcoin instance with rpc_url: {} on network: {}",
-            rpc_url, network
-        );
+        match self
+            .submit_package(package.signed_raw_txns, package.force)
+            .await
+        {
+            Ok(package_result) => {
+                result.is_error = false;
+                result.data = Some(package_result);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
 
-        Ok(Self {
-            rpc_url: rpc_url.parse::<Url>()?,
-            network,
-            bitcoin_txid_regex: Regex::new(BITCOIN_TXID_REGEX)?,
-            explorer_url: explorer_url.parse::<Url>()?,
-            sign_txn,
-        })
+        result
     }
 
-    async fn get_wallet_balance(
+    async fn test_transaction(
         &self,
-        address: String,
-    ) -> Result<WalletBalanceResponseData, BtcApiError> {
-        // Validate the address
-        if !is_valid_bitcoin_address(&address, self.network) {
-            return Err(BtcApiError::InvalidAddress(format!(
-                "Invalid address: {} on network: {}",
-                address, self.network
-            )));
+        transaction: TestTransactionParams,
+    ) -> TestTransactionResponse {
+        let mut result = TestTransactionResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.test_transaction(transaction.signed_raw_txn).await {
+            Ok(test_result) => {
+                result.is_error = false;
+                result.data = Some(test_result);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
         }
 
-        let url = self.rpc_url.join(&format!("address/{}", address))?;
+        result
+    }
 
-        let blockstream_response = reqwest::get(url).await?.text().await?;
+    async fn release_utxos(&self, release: ReleaseUtxosParams) -> ReleaseUtxosResponse {
+        let mut result = ReleaseUtxosResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
 
-        let blockstream_wallet_balance =
-            serde_json::from_str::<BlockstreamWalletBalance>(&blockstream_response)?;
+        match self.release_utxos(release) {
+            Ok(released_utxos) => {
+                result.is_error = false;
+                result.data = Some(released_utxos);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
 
-        // This can be negative also if the wallet has more unconfirmedincoming transactions than outgoing
-        let confirmed_balance = blockstream_wallet_balance.get_confirmed_balance();
+        result
+    }
 
-        // This can be negative also if the wallet has more unconfirmed outgoing transactions than incoming
-        let unconfirmed_balance = blockstream_wallet_balance.get_unconfirmed_balance();
+    async fn parse_payment_uri(&self, params: ParsePaymentUriParams) -> PaymentUriResponse {
+        let mut result = PaymentUriResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
 
-        let total_balance = confirmed_balance + unconfirmed_balance;
+        match self.parse_payment_uri(params.uri) {
+            Ok(payment_uri) => {
+                result.is_error = false;
+                result.data = Some(payment_uri);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
 
-        Ok(WalletBalanceResponseData {
-            confirmed_balance,
-            unconfirmed_balance,
-            total_balance,
-        })
+        result
     }
 
-    async fn get_raw_transaction(
-        &self,
-        transaction_hash: String,
-    ) -> Result<ValidateTransactionHashResponseData, BtcApiError> {
-        //call blockchain api to get raw transaction
-
-        let url = BLOCKCHAIN_API_RAW_TRANSACTION_URL
-            .parse::<Url>()?
-            .join(&transaction_hash)?;
+    async fn create_payment_uri(&self, params: CreatePaymentUriParams) -> PaymentUriResponse {
+        let mut result = PaymentUriResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
 
-        let response_body = reqwest::get(url).await?.text().await?;
+        match self.create_payment_uri(params) {
+            Ok(payment_uri) => {
+                result.is_error = false;
+                result.data = Some(payment_uri);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
 
-        let blockchaincom_response = serde_json::from_str::<BlockchaincomResponse>(&response_body)?;
+        result
+    }
 
-        match blockchaincom_response {
-            BlockchaincomResponse::RawTxn(blockchaincom_raw_txn) => {
-                let txn_block_index = blockchaincom_raw_txn.block_index;
-                let txn_block_height = blockchaincom_raw_txn.block_height;
-                let double_spend = blockchaincom_raw_txn.double_spend;
-                let rbf = blockchaincom_raw_txn.rbf;
+    async fn sign_message(&self, params: SignMessageParams) -> SignMessageResponse {
+        let mut result = SignMessageResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
 
-                match (txn_block_index, txn_block_height, double_spend, rbf) {
-                    (None, None, true, _) => {
-                        //CASE : INVALID TXN
+        match self.sign_message(params) {
+            Ok(signed_message) => {
+                result.is_error = false;
+                result.data = Some(signed_message);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn verify_message(&self, params: VerifyMessageParams) -> VerifyMessageResponse {
+        let mut result = VerifyMessageResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.verify_message(params) {
+            Ok(verified_message) => {
+                result.is_error = false;
+                result.data = Some(verified_message);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn get_wallet_balance(
+        &self,
+        address: String,
+        currency: Option<String>,
+    ) -> WalletBalanceResponse {
+        let mut result = WalletBalanceResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.get_wallet_balance(address, currency).await {
+            Ok(wallet_balance) => {
+                result.is_error = false;
+                result.data = Some(wallet_balance);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn get_wallet_balances(&self, params: WalletBalancesParams) -> WalletBalancesResponse {
+        let mut result = WalletBalancesResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.get_wallet_balances(params).await {
+            Ok(wallet_balances) => {
+                result.is_error = false;
+                result.data = Some(wallet_balances);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn get_address_transactions(
+        &self,
+        address: String,
+        last_seen_txid: Option<String>,
+        currency: Option<String>,
+    ) -> AddressTransactionsResponse {
+        let mut result = AddressTransactionsResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self
+            .get_address_transactions(address, last_seen_txid, currency)
+            .await
+        {
+            Ok(address_transactions) => {
+                result.is_error = false;
+                result.data = Some(address_transactions);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+
+    async fn get_address_info(&self, address: String) -> AddressInfoResponse {
+        // An invalid address is described in the data, so that callers get the reason
+        AddressInfoResponse {
+            is_error: false,
+            data: Some(get_address_info(&address, self.network)),
+            error_msg: None,
+        }
+    }
+
+    async fn get_address_utxos(
+        &self,
+        address: String,
+        filters: AddressUtxosParams,
+    ) -> AddressUtxosResponse {
+        let mut result = AddressUtxosResponse {
+            is_error: true,
+            data: None,
+            error_msg: None,
+        };
+
+        match self.get_address_utxos(address, filters).await {
+            Ok(address_utxos) => {
+                result.is_error = false;
+                result.data = Some(address_utxos);
+            }
+            Err(err) => {
+                result.error_msg = Some(err.to_string());
+            }
+        }
+
+        result
+    }
+}
+
+impl Bitcoin {
+    pub fn new(rpc_url: &str, variant: &ChainVariant, sign_txn: bool) -> Result<Self, BtcApiError> {
+        let (network, explorer_url, mempool_space_url) = match variant {
+            ChainVariant::Mainnet => (
+                Network::Bitcoin,
+                BLOCKSTREAM_MAINNET_EXPLORER_URL,
+                MEMPOOL_SPACE_MAINNET_API_URL,
+            ),
+            ChainVariant::Testnet => (
+                Network::Testnet,
+                BLOCKSTREAM_TESTNET_EXPLORER_URL,
+                MEMPOOL_SPACE_TESTNET_API_URL,
+            ),
+        };
+
+        info!(
+            "Creating Bitcoin instance with rpc_url: {} on network: {}",
+            rpc_url, network
+        );
+
+        let rpc_url = rpc_url.parse::<Url>()?;
+
+        let fee_estimation = FeeEstimationConfig::default();
+
+        Ok(Self {
+            broadcast_providers: vec![BroadcastProvider::Esplora(rpc_url.clone())],
+            fee_estimator: FeeEstimator::new(
+                vec![
+                    FeeSource::MempoolSpace(mempool_space_url.parse::<Url>()?),
+                    FeeSource::Esplora(rpc_url.clone()),
+                ],
+                Duration::from_secs(fee_estimation.cache_ttl_secs),
+                fee_estimation.min_fee_rate,
+                fee_estimation.max_deviation,
+            ),
+            rpc_url,
+            network,
+            bitcoin_txid_regex: Regex::new(BITCOIN_TXID_REGEX)?,
+            explorer_url: explorer_url.parse::<Url>()?,
+            sign_txn,
+            utxo_locks: UtxoLocks::new(DEFAULT_UTXO_LOCK_TTL),
+            broadcast_policy: BroadcastPolicyConfig::default(),
+            bitcoind: None,
+            batch_requests: BatchRequestsConfig::default(),
+            // Prices do not depend on the network
+            price_feed: Arc::new(HttpPriceFeed::new(
+                MEMPOOL_SPACE_MAINNET_API_URL.parse::<Url>()?,
+            )),
+        })
+    }
+
+    pub(crate) fn with_utxo_lock_ttl(mut self, ttl: Duration) -> Self {
+        self.utxo_locks = UtxoLocks::new(ttl);
+        self
+    }
+
+    pub(crate) fn with_broadcast_policy(mut self, broadcast_policy: BroadcastPolicyConfig) -> Self {
+        self.broadcast_policy = broadcast_policy;
+        self
+    }
+
+    pub(crate) fn with_batch_requests(mut self, batch_requests: BatchRequestsConfig) -> Self {
+        self.batch_requests = batch_requests;
+        self
+    }
+
+    pub(crate) fn with_bitcoind(
+        mut self,
+        bitcoind: Option<&BitcoindConfig>,
+    ) -> Result<Self, BtcApiError> {
+        if let Some(bitcoind) = bitcoind {
+            info!("Connecting to bitcoind at: {}", bitcoind.url);
+
+            let auth = Auth::UserPass(bitcoind.user.clone(), bitcoind.password.clone());
+            self.bitcoind = Some(Arc::new(bitcoincore_rpc::Client::new(&bitcoind.url, auth)?));
+        }
+
+        Ok(self)
+    }
+
+    // Replaces the default rpc_url broadcast provider, bitcoind has to be configured before
+    pub(crate) fn with_broadcast_providers(
+        mut self,
+        broadcast_providers: &[BroadcastProviderConfig],
+    ) -> Result<Self, BtcApiError> {
+        if broadcast_providers.is_empty() {
+            return Ok(self);
+        }
+
+        self.broadcast_providers = broadcast_providers
+            .iter()
+            .map(|provider| match provider {
+                BroadcastProviderConfig::Esplora { url } => {
+                    Ok(BroadcastProvider::Esplora(url.parse::<Url>()?))
+                }
+                BroadcastProviderConfig::Bitcoind => self
+                    .bitcoind
+                    .clone()
+                    .map(BroadcastProvider::Bitcoind)
+                    .ok_or_else(|| {
+                        BtcApiError::ConfigLoadError(
+                            "bitcoind broadcast provider requires the bitcoind config".to_string(),
+                        )
+                    }),
+            })
+            .collect::<Result<Vec<BroadcastProvider>, BtcApiError>>()?;
+
+        Ok(self)
+    }
+
+    // Replaces the default fee sources when some are configured, bitcoind has to be configured
+    // before
+    pub(crate) fn with_fee_estimation(
+        mut self,
+        fee_estimation: &FeeEstimationConfig,
+    ) -> Result<Self, BtcApiError> {
+        let sources = if fee_estimation.sources.is_empty() {
+            self.fee_estimator.sources().to_vec()
+        } else {
+            fee_estimation
+                .sources
+                .iter()
+                .map(|source| match source {
+                    FeeSourceConfig::MempoolSpace { url } => {
+                        Ok(FeeSource::MempoolSpace(url.parse::<Url>()?))
+                    }
+                    FeeSourceConfig::Esplora { url } => Ok(FeeSource::Esplora(url.parse::<Url>()?)),
+                    FeeSourceConfig::Bitcoind => self
+                        .bitcoind
+                        .clone()
+                        .map(FeeSource::Bitcoind)
+                        .ok_or_else(|| {
+                            BtcApiError::ConfigLoadError(
+                                "bitcoind fee source requires the bitcoind config".to_string(),
+                            )
+                        }),
+                })
+                .collect::<Result<Vec<FeeSource>, BtcApiError>>()?
+        };
+
+        self.fee_estimator = FeeEstimator::new(
+            sources,
+            Duration::from_secs(fee_estimation.cache_ttl_secs),
+            fee_estimation.min_fee_rate,
+            fee_estimation.max_deviation,
+        );
+
+        Ok(self)
+    }
+
+    // Replaces the default mempool.space price feed when one is configured
+    pub(crate) fn with_price_feed(
+        mut self,
+        price_feed: Option<&PriceFeedConfig>,
+    ) -> Result<Self, BtcApiError> {
+        match price_feed {
+            Some(PriceFeedConfig::File { path }) => {
+                info!("Loading fiat prices from: {}", path);
+                self.price_feed = Arc::new(FilePriceFeed::load(path)?);
+            }
+            Some(PriceFeedConfig::Http { url }) => {
+                self.price_feed = Arc::new(HttpPriceFeed::new(url.parse::<Url>()?));
+            }
+            None => {}
+        }
+
+        Ok(self)
+    }
+
+    async fn get_network_fee(&self) -> Result<NetworkFeeResponseData, BtcApiError> {
+        Ok(self.fee_estimator.get_network_fee().await)
+    }
+
+    // Esplora first, bitcoind when it fails and is configured
+    async fn get_mempool(&self) -> Result<MempoolResponseData, BtcApiError> {
+        let err = match self.get_esplora_mempool().await {
+            Ok(mempool) => return Ok(mempool),
+            Err(err) => err,
+        };
+
+        let Some(bitcoind) = self.bitcoind.clone() else {
+            return Err(err);
+        };
+        error!(
+            "Failed to get the mempool from esplora, falling back to bitcoind: {}",
+            err
+        );
+
+        let mempool_info = tokio::task::spawn_blocking(move || bitcoind.get_mempool_info())
+            .await
+            .map_err(|err| BtcApiError::ExternalApiError(err.to_string()))??;
+
+        Ok(MempoolResponseData {
+            count: mempool_info.size as u64,
+            vsize: mempool_info.bytes as u64,
+            total_fee: mempool_info.total_fee.map(|total_fee| total_fee.to_sat()),
+            fee_histogram: vec![],
+            // BTC/kvB
+            mempool_min_fee: Some(mempool_info.mempool_min_fee.to_sat() as f64 / 1000.0),
+            source: "bitcoind".to_string(),
+        })
+    }
+
+    async fn get_esplora_mempool(&self) -> Result<MempoolResponseData, BtcApiError> {
+        let url = self.rpc_url.join("mempool")?;
+        let mempool = reqwest::get(url)
+            .await?
+            .json::<BlockstreamMempool>()
+            .await?;
+
+        Ok(MempoolResponseData {
+            count: mempool.count,
+            vsize: mempool.vsize,
+            total_fee: Some(mempool.total_fee),
+            fee_histogram: mempool
+                .fee_histogram
+                .into_iter()
+                .map(|(fee_rate, vsize)| FeeHistogramBucket { fee_rate, vsize })
+                .collect(),
+            mempool_min_fee: None,
+            source: format!("esplora({})", url_host(&self.rpc_url)),
+        })
+    }
+
+    async fn get_network_fee_for_target(
+        &self,
+        params: NetworkFeeParams,
+    ) -> Result<NetworkFeeResponseData, BtcApiError> {
+        let target_blocks = params.get_target_blocks()?;
+        let mut network_fee = self.get_network_fee().await?;
+
+        if let Some(target_blocks) = target_blocks {
+            network_fee.target = Some(self.fee_estimator.get_target_fee_rate(target_blocks).await);
+        }
+
+        Ok(network_fee)
+    }
+
+    async fn get_wallet_balance(
+        &self,
+        address: String,
+        currency: Option<String>,
+    ) -> Result<WalletBalanceResponseData, BtcApiError> {
+        let price = match currency {
+            Some(currency) => Some(self.get_fiat_price(&currency, None).await?),
+            None => None,
+        };
+
+        self.get_wallet_balance_at_price(address, price.as_ref())
+            .await
+    }
+
+    // The addresses are fetched concurrently, at most max_concurrency at a time. The fiat
+    // price is fetched once for the whole batch.
+    async fn get_wallet_balances(
+        &self,
+        params: WalletBalancesParams,
+    ) -> Result<WalletBalancesResponseData, BtcApiError> {
+        self.check_batch_size(params.wallet_addresses.len())?;
+
+        let price = match params.currency {
+            Some(currency) => Some(self.get_fiat_price(&currency, None).await?),
+            None => None,
+        };
+
+        let balances = stream::iter(params.wallet_addresses)
+            .map(|wallet_address| {
+                let price = price.as_ref();
+                async move {
+                    let result = self
+                        .get_wallet_balance_at_price(wallet_address.clone(), price)
+                        .await;
+
+                    AddressWalletBalance {
+                        wallet_address,
+                        is_error: result.is_err(),
+                        error_msg: result.as_ref().err().map(BtcApiError::to_string),
+                        data: result.ok(),
+                    }
+                }
+            })
+            .buffered(self.batch_requests.max_concurrency.max(1))
+            .collect::<Vec<AddressWalletBalance>>()
+            .await;
+
+        Ok(WalletBalancesResponseData { balances })
+    }
+
+    fn check_batch_size(&self, size: usize) -> Result<(), BtcApiError> {
+        if size == 0 || size > self.batch_requests.max_batch_size {
+            return Err(BtcApiError::InvalidBatch(format!(
+                "Expected 1 to {} items, got {}",
+                self.batch_requests.max_batch_size, size
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn get_wallet_balance_at_price(
+        &self,
+        address: String,
+        price: Option<&FiatPrice>,
+    ) -> Result<WalletBalanceResponseData, BtcApiError> {
+        // Validate the address
+        if !is_valid_bitcoin_address(&address, self.network) {
+            return Err(BtcApiError::InvalidAddress(format!(
+                "Invalid address: {} on network: {}",
+                address, self.network
+            )));
+        }
+
+        let url = self.rpc_url.join(&format!("address/{}", address))?;
+
+        let blockstream_response = reqwest::get(url).await?.text().await?;
+
+        let blockstream_wallet_balance =
+            serde_json::from_str::<BlockstreamWalletBalance>(&blockstream_response)?;
+
+        // This can be negative also if the wallet has more unconfirmedincoming transactions than outgoing
+        let confirmed_balance = blockstream_wallet_balance.get_confirmed_balance();
+
+        // This can be negative also if the wallet has more unconfirmed outgoing transactions than incoming
+        let unconfirmed_balance = blockstream_wallet_balance.get_unconfirmed_balance();
+
+        let total_balance = confirmed_balance
+            .checked_add(unconfirmed_balance)
+            .ok_or_else(|| {
+                BtcApiError::ExternalApiError(format!("Balance overflow for: {}", address))
+            })?;
+
+        let fiat = price.map(|price| FiatBalance {
+            confirmed_balance: to_fiat(confirmed_balance.to_btc(), price),
+            unconfirmed_balance: to_fiat(unconfirmed_balance.to_btc(), price),
+            total_balance: to_fiat(total_balance.to_btc(), price),
+            price: price.clone(),
+        });
+
+        Ok(WalletBalanceResponseData {
+            confirmed_balance,
+            unconfirmed_balance,
+            total_balance,
+            fiat,
+            btc: None,
+        })
+    }
+
+    // Mined transactions are priced at their block time, the others at the current price. The
+    // prices are fetched at most max_concurrency at a time.
+    async fn get_address_transactions(
+        &self,
+        address: String,
+        last_seen_txid: Option<String>,
+        currency: Option<String>,
+    ) -> Result<AddressTransactionsResponseData, BtcApiError> {
+        // Validate the address
+        if !is_valid_bitcoin_address(&address, self.network) {
+            return Err(BtcApiError::InvalidAddress(format!(
+                "Invalid address: {} on network: {}",
+                address, self.network
+            )));
+        }
+
+        // First page contains the mempool transactions followed by the newest confirmed ones,
+        // the following pages only contain confirmed transactions older than last_seen_txid
+        let url = match &last_seen_txid {
+            Some(last_seen_txid) => {
+                if !self.bitcoin_txid_regex.is_match(last_seen_txid) {
+                    return Err(BtcApiError::InvalidTxid(last_seen_txid.clone()));
+                }
+                self.rpc_url
+                    .join(&format!("address/{}/txs/chain/{}", address, last_seen_txid))?
+            }
+            None => self.rpc_url.join(&format!("address/{}/txs", address))?,
+        };
+
+        let blockstream_response = reqwest::get(url).await?.text().await?;
+
+        let blockstream_txns = serde_json::from_str::<Vec<BlockstreamTxn>>(&blockstream_response)?;
+
+        let confirmed_txns = blockstream_txns
+            .iter()
+            .filter(|txn| txn.status.confirmed)
+            .collect::<Vec<&BlockstreamTxn>>();
+
+        let next_cursor = if confirmed_txns.len() >= BLOCKSTREAM_CHAIN_TXNS_PAGE_SIZE {
+            confirmed_txns.last().map(|txn| txn.txid.clone())
+        } else {
+            None
+        };
+
+        let mut transactions = blockstream_txns
+            .iter()
+            .map(|txn| AddressTransaction {
+                txn_hash: txn.txid.clone(),
+                txn_status: if txn.status.confirmed {
+                    TxnStatus::Confirmed
+                } else {
+                    TxnStatus::Pending
+                },
+                net_amount: txn.get_net_amount(&address),
+                consumed_fees: Amount::from_sat(txn.fee),
+                block_height: txn.status.block_height,
+                block_time: txn.status.block_time,
+                input_txns: txn.get_input_txns(),
+                output_txns: txn.get_output_txns(),
+                fiat: None,
+                btc: None,
+            })
+            .collect::<Vec<AddressTransaction>>();
+
+        if let Some(currency) = currency {
+            let block_times = transactions
+                .iter()
+                .map(|txn| txn.block_time)
+                .collect::<Vec<Option<u64>>>();
+
+            let prices = stream::iter(block_times)
+                .map(|block_time| {
+                    let currency = currency.clone();
+                    async move { self.get_fiat_price(&currency, block_time).await }
+                })
+                .buffered(self.batch_requests.max_concurrency.max(1))
+                .try_collect::<Vec<FiatPrice>>()
+                .await?;
+
+            for (txn, price) in transactions.iter_mut().zip(prices) {
+                txn.fiat = Some(FiatAddressTransactionValue {
+                    net_amount: to_fiat(txn.net_amount.to_btc(), &price),
+                    consumed_fees: to_fiat(txn.consumed_fees.to_btc(), &price),
+                    price,
+                });
+            }
+        }
+
+        Ok(AddressTransactionsResponseData {
+            address,
+            transactions,
+            next_cursor,
+        })
+    }
+
+    async fn get_address_utxos(
+        &self,
+        address: String,
+        filters: AddressUtxosParams,
+    ) -> Result<AddressUtxosResponseData, BtcApiError> {
+        // Validate the address
+        if !is_valid_bitcoin_address(&address, self.network) {
+            return Err(BtcApiError::InvalidAddress(format!(
+                "Invalid address: {} on network: {}",
+                address, self.network
+            )));
+        }
+
+        // All the UTXOs of an address share the same script type
+        let script_type = Address::from_str(&address)?
+            .require_network(self.network)?
+            .address_type()
+            .map(|address_type| address_type.to_string())
+            .unwrap_or("unknown".to_string());
+
+        let mut utxos = self.fetch_utxos(&address).await?;
+        utxos.sort_by_key(|utxo| std::cmp::Reverse(utxo.value));
+
+        // The confirmations are best-effort, the UTXOs are listed without them
+        let tip_height = match self.get_tip_height().await {
+            Ok(tip_height) => Some(tip_height),
+            Err(err) => {
+                warn!(
+                    "Unable to fetch the tip height for UTXO confirmations: {}",
+                    err
+                );
+                None
+            }
+        };
+        let locked_outpoints = self.utxo_locks.locked_outpoints();
+
+        let utxos = utxos
+            .into_iter()
+            .map(|utxo| AddressUtxo {
+                confirmations: match tip_height {
+                    Some(tip_height) => Some(utxo.get_confirmations(tip_height)),
+                    None if utxo.is_confirmed() => None,
+                    None => Some(0),
+                },
+                state: match utxo.get_outpoint() {
+                    Ok(outpoint)
+                        if utxo.is_confirmed() && !locked_outpoints.contains(&outpoint) =>
+                    {
+                        UtxoState::Spendable
+                    }
+                    _ => UtxoState::Frozen,
+                },
+                txid: utxo.txid,
+                vout: utxo.vout,
+                value: Amount::from_sat(utxo.value),
+                block_height: utxo.status.block_height,
+                script_type: script_type.clone(),
+                btc: None,
+            })
+            // A confirmed UTXO has at least one confirmation when the tip height is unavailable
+            .filter(|utxo| {
+                utxo.confirmations.unwrap_or(1) >= filters.min_confirmations.unwrap_or(0)
+            })
+            .filter(|utxo| utxo.value >= filters.min_value.unwrap_or_default())
+            .collect::<Vec<AddressUtxo>>();
+
+        let total_value = utxos
+            .iter()
+            .map(|utxo| utxo.value)
+            .try_fold(Amount::ZERO, Amount::checked_add)
+            .ok_or_else(|| {
+                BtcApiError::ExternalApiError("Sum of the UTXO values overflows".to_string())
+            })?;
+
+        Ok(AddressUtxosResponseData {
+            address,
+            total_value,
+            utxos,
+            btc: None,
+        })
+    }
+
+    // Confirmations of a mined transaction are left unset, see get_raw_transaction_with_fiat
+    async fn get_raw_transaction(
+        &self,
+        transaction_hash: String,
+    ) -> Result<ValidateTransactionHashResponseData, BtcApiError> {
+        //call blockchain api to get raw transaction
+
+        let url = BLOCKCHAIN_API_RAW_TRANSACTION_URL
+            .parse::<Url>()?
+            .join(&transaction_hash)?;
+
+        let response_body = reqwest::get(url).await?.text().await?;
+
+        let blockchaincom_response = serde_json::from_str::<BlockchaincomResponse>(&response_body)?;
+
+        match blockchaincom_response {
+            BlockchaincomResponse::RawTxn(blockchaincom_raw_txn) => {
+                let txn_block_index = blockchaincom_raw_txn.block_index;
+                let txn_block_height = blockchaincom_raw_txn.block_height;
+                let double_spend = blockchaincom_raw_txn.double_spend;
+                let rbf = blockchaincom_raw_txn.rbf;
+
+                match (txn_block_index, txn_block_height, double_spend, rbf) {
+                    (None, None, true, _) => {
+                        //CASE : INVALID TXN
                         //Transaction is invalid/cacelled if
                         //txn_block_index is None
                         //txn_block_height is None
@@ -282,14 +1227,18 @@ impl Bitcoin {
                             txn_hash: transaction_hash,
                             txn_status: TxnStatus::Cancelled,
                             txn_status_flag: 1,
+                            confirmations: Some(0),
                             txn_data: Some(TransactionData {
                                 block_index: None,
                                 block_height: None,
+                                txn_time: blockchaincom_raw_txn.time,
                                 consumed_fees: blockchaincom_raw_txn.get_total_fee(),
                                 txn_input_amount: blockchaincom_raw_txn.get_total_input_amount(),
                                 txn_output_amount: blockchaincom_raw_txn.get_total_output_amount(),
                                 input_txns: blockchaincom_raw_txn.get_input_txns(),
                                 output_txns: blockchaincom_raw_txn.get_output_txns(),
+                                fiat: None,
+                                btc: None,
                             }),
                         };
 
@@ -307,14 +1256,18 @@ impl Bitcoin {
                             txn_hash: transaction_hash,
                             txn_status: TxnStatus::Confirmed,
                             txn_status_flag: 0,
+                            confirmations: None,
                             txn_data: Some(TransactionData {
                                 block_index: Some(block_index),
                                 block_height: Some(block_height),
+                                txn_time: blockchaincom_raw_txn.time,
                                 consumed_fees: blockchaincom_raw_txn.get_total_fee(),
                                 txn_input_amount: blockchaincom_raw_txn.get_total_input_amount(),
                                 txn_output_amount: blockchaincom_raw_txn.get_total_output_amount(),
                                 input_txns: blockchaincom_raw_txn.get_input_txns(),
                                 output_txns: blockchaincom_raw_txn.get_output_txns(),
+                                fiat: None,
+                                btc: None,
                             }),
                         };
 
@@ -332,14 +1285,18 @@ impl Bitcoin {
                             txn_hash: transaction_hash,
                             txn_status: TxnStatus::Pending,
                             txn_status_flag: 2,
+                            confirmations: Some(0),
                             txn_data: Some(TransactionData {
                                 block_index: None,
                                 block_height: None,
+                                txn_time: blockchaincom_raw_txn.time,
                                 consumed_fees: blockchaincom_raw_txn.get_total_fee(),
                                 txn_input_amount: blockchaincom_raw_txn.get_total_input_amount(),
                                 txn_output_amount: blockchaincom_raw_txn.get_total_output_amount(),
                                 input_txns: blockchaincom_raw_txn.get_input_txns(),
                                 output_txns: blockchaincom_raw_txn.get_output_txns(),
+                                fiat: None,
+                                btc: None,
                             }),
                         };
 
@@ -364,10 +1321,298 @@ impl Bitcoin {
         }
     }
 
+    // The transactions are fetched concurrently, at most max_concurrency at a time, and share
+    // one best-effort tip height lookup. Malformed hashes fail without a request to the provider.
+    async fn validate_transaction_hashes(
+        &self,
+        params: ValidateTransactionHashesParams,
+    ) -> Result<ValidateTransactionHashesResponseData, BtcApiError> {
+        self.check_batch_size(params.transaction_hashes.len())?;
+
+        let tip_height = self.get_confirmations_tip_height().await;
+
+        let transactions = stream::iter(params.transaction_hashes)
+            .map(|transaction_hash| {
+                let currency = params.currency.clone();
+                async move {
+                    let result = if self.bitcoin_txid_regex.is_match(&transaction_hash) {
+                        self.get_raw_transaction_with_fiat(
+                            transaction_hash.clone(),
+                            currency,
+                            tip_height,
+                        )
+                        .await
+                    } else {
+                        Err(BtcApiError::InvalidTxid(transaction_hash.clone()))
+                    };
+
+                    TransactionHashValidation {
+                        transaction_hash,
+                        is_error: result.is_err(),
+                        error_msg: result.as_ref().err().map(BtcApiError::to_string),
+                        data: result.ok(),
+                    }
+                }
+            })
+            .buffered(self.batch_requests.max_concurrency.max(1))
+            .collect::<Vec<TransactionHashValidation>>()
+            .await;
+
+        Ok(ValidateTransactionHashesResponseData { transactions })
+    }
+
+    async fn validate_transaction_hash(
+        &self,
+        transaction_hash: String,
+        currency: Option<String>,
+    ) -> Result<ValidateTransactionHashResponseData, BtcApiError> {
+        let tip_height = self.get_confirmations_tip_height().await;
+
+        self.get_raw_transaction_with_fiat(transaction_hash, currency, tip_height)
+            .await
+    }
+
+    // A failed tip lookup leaves the confirmations unset rather than failing the status check
+    async fn get_confirmations_tip_height(&self) -> Option<u64> {
+        match self.get_tip_height().await {
+            Ok(tip_height) => Some(tip_height),
+            Err(err) => {
+                error!("Unable to fetch the tip height for confirmations: {}", err);
+                None
+            }
+        }
+    }
+
+    // The block of a mined transaction is looked up on the configured network, it gives the
+    // confirmations and the block time the transaction is priced at. Transactions which are not
+    // mined are priced at the time blockchain.info received them.
+    async fn get_raw_transaction_with_fiat(
+        &self,
+        transaction_hash: String,
+        currency: Option<String>,
+        tip_height: Option<u64>,
+    ) -> Result<ValidateTransactionHashResponseData, BtcApiError> {
+        let mut validate_txn_data = self.get_raw_transaction(transaction_hash).await?;
+
+        let block_status = match validate_txn_data.txn_status {
+            TxnStatus::Confirmed => self.get_txn_block_status(&validate_txn_data.txn_hash).await,
+            _ => None,
+        };
+
+        if let (Some(block_status), Some(tip_height)) = (&block_status, tip_height) {
+            validate_txn_data.confirmations = Some(block_status.get_confirmations(tip_height));
+        }
+
+        let is_confirmed = validate_txn_data.txn_status == TxnStatus::Confirmed;
+        let (Some(currency), Some(txn_data)) = (currency, validate_txn_data.txn_data.as_mut())
+        else {
+            return Ok(validate_txn_data);
+        };
+
+        let price_time = match block_status.and_then(|block_status| block_status.block_time) {
+            Some(block_time) => block_time,
+            None if is_confirmed => {
+                return Err(BtcApiError::ExternalApiError(format!(
+                    "Block time of txn hash {} is unavailable",
+                    validate_txn_data.txn_hash
+                )));
+            }
+            None => txn_data.txn_time,
+        };
+
+        let price = self.get_fiat_price(&currency, Some(price_time)).await?;
+        txn_data.fiat = Some(FiatTransactionValue {
+            consumed_fees: to_fiat(txn_data.consumed_fees.to_btc(), &price),
+            txn_input_amount: to_fiat(txn_data.txn_input_amount.to_btc(), &price),
+            txn_output_amount: to_fiat(txn_data.txn_output_amount.to_btc(), &price),
+            price,
+        });
+
+        Ok(validate_txn_data)
+    }
+
+    // Best-effort, a failed lookup is logged and leaves the confirmations and block time unset
+    async fn get_txn_block_status(&self, transaction_hash: &str) -> Option<Status> {
+        match self.get_txn_status(transaction_hash).await {
+            Ok(status) => Some(status),
+            Err(err) => {
+                error!(
+                    "Unable to fetch the block status of txn hash {}: {}",
+                    transaction_hash, err
+                );
+                None
+            }
+        }
+    }
+
+    async fn get_txn_status(&self, transaction_hash: &str) -> Result<Status, BtcApiError> {
+        let url = self
+            .rpc_url
+            .join(&format!("tx/{}/status", transaction_hash))?;
+
+        let response = reqwest::get(url).await?;
+        if !response.status().is_success() {
+            return Err(BtcApiError::ExternalApiError(format!(
+                "Transaction {} not found",
+                transaction_hash
+            )));
+        }
+
+        Ok(response.json::<Status>().await?)
+    }
+
+    async fn get_fiat_price(
+        &self,
+        currency: &str,
+        timestamp: Option<u64>,
+    ) -> Result<FiatPrice, BtcApiError> {
+        if currency.is_empty() || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(BtcApiError::PriceFeedError(format!(
+                "Invalid currency: {}",
+                currency
+            )));
+        }
+
+        self.price_feed.get_price(currency, timestamp).await
+    }
+
+    async fn get_block_tip(&self) -> Result<BlockResponseData, BtcApiError> {
+        let tip_block = self.get_tip_block().await?;
+        let tip_height = tip_block.height;
+
+        self.get_block_data(tip_block, tip_height).await
+    }
+
+    async fn get_block(&self, hash_or_height: String) -> Result<BlockResponseData, BtcApiError> {
+        let block = self.get_blockstream_block(&hash_or_height).await?;
+
+        let tip_height = self.get_tip_height().await?;
+        self.get_block_data(block, tip_height).await
+    }
+
+    async fn get_blockstream_block(
+        &self,
+        hash_or_height: &str,
+    ) -> Result<BlockstreamBlock, BtcApiError> {
+        let hash = self.get_block_hash(hash_or_height).await?;
+
+        let url = self.rpc_url.join(&format!("block/{}", hash))?;
+        let response = reqwest::get(url).await?;
+        if !response.status().is_success() {
+            return Err(BtcApiError::BlockNotFound(hash));
+        }
+
+        Ok(response.json::<BlockstreamBlock>().await?)
+    }
+
+    async fn get_block_txids(&self, hash: String) -> Result<BlockTxidsResponseData, BtcApiError> {
+        if !self.bitcoin_txid_regex.is_match(&hash) {
+            return Err(BtcApiError::BlockNotFound(format!(
+                "Invalid block hash: {}",
+                hash
+            )));
+        }
+
+        let url = self.rpc_url.join(&format!("block/{}/txids", hash))?;
+        let response = reqwest::get(url).await?;
+        if !response.status().is_success() {
+            return Err(BtcApiError::BlockNotFound(hash));
+        }
+
+        Ok(BlockTxidsResponseData {
+            txids: response.json::<Vec<String>>().await?,
+            hash,
+        })
+    }
+
+    // Block hashes are passed through, heights are resolved to the hash of the active chain
+    async fn get_block_hash(&self, hash_or_height: &str) -> Result<String, BtcApiError> {
+        if self.bitcoin_txid_regex.is_match(hash_or_height) {
+            return Ok(hash_or_height.to_lowercase());
+        }
+
+        let height = hash_or_height.parse::<u64>().map_err(|_| {
+            BtcApiError::BlockNotFound(format!(
+                "Expected a block hash or height: {}",
+                hash_or_height
+            ))
+        })?;
+
+        let url = self.rpc_url.join(&format!("block-height/{}", height))?;
+        let response = reqwest::get(url).await?;
+        if !response.status().is_success() {
+            return Err(BtcApiError::BlockNotFound(format!("Height {}", height)));
+        }
+
+        Ok(response.text().await?.trim().to_string())
+    }
+
+    async fn get_block_data(
+        &self,
+        block: BlockstreamBlock,
+        tip_height: u64,
+    ) -> Result<BlockResponseData, BtcApiError> {
+        // Esplora has no fee statistics, getblockstats is only used when bitcoind is configured
+        let median_fee_rate = match self.bitcoind.clone() {
+            Some(bitcoind) => {
+                let height = block.height;
+                let block_stats = tokio::task::spawn_blocking(move || {
+                    bitcoind.get_block_stats_fields(height, &[BlockStatsFields::FeeRatePercentiles])
+                })
+                .await
+                .map_err(|err| BtcApiError::ExternalApiError(err.to_string()))?;
+
+                match block_stats {
+                    Ok(block_stats) => block_stats
+                        .fee_rate_percentiles
+                        .map(|percentiles| percentiles.fr_50th.to_sat() as f64),
+                    Err(err) => {
+                        error!("Failed to get the block stats of {}: {}", block.id, err);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        Ok(BlockResponseData {
+            confirmations: (tip_height + 1).saturating_sub(block.height),
+            hash: block.id,
+            height: block.height,
+            timestamp: block.timestamp,
+            median_time: block.mediantime,
+            tx_count: block.tx_count,
+            size: block.size,
+            weight: block.weight,
+            previous_block_hash: block.previousblockhash,
+            median_fee_rate,
+        })
+    }
+
+    async fn get_tip_height(&self) -> Result<u64, BtcApiError> {
+        let url = self.rpc_url.join("blocks/tip/height")?;
+
+        let response_text = reqwest::get(url).await?.text().await?;
+
+        response_text.trim().parse::<u64>().map_err(|_| {
+            BtcApiError::ExternalApiError(format!("Invalid tip height: {}", response_text))
+        })
+    }
+
+    async fn get_tip_block(&self) -> Result<BlockstreamBlock, BtcApiError> {
+        let url = self.rpc_url.join("blocks/tip/hash")?;
+        let tip_hash = reqwest::get(url).await?.text().await?;
+
+        let url = self.rpc_url.join(&format!("block/{}", tip_hash.trim()))?;
+        let tip_block = reqwest::get(url).await?.json::<BlockstreamBlock>().await?;
+
+        Ok(tip_block)
+    }
+
     async fn create_transaction(
         &self,
         transaction_params: CreateTransactionParams,
-    ) -> Result<(Transaction, Vec<BlockstreamUtxo>), BtcApiError> {
+    ) -> Result<CreateTransactionResponseData, BtcApiError> {
         // Validate the addresses
         if !is_valid_bitcoin_address(&transaction_params.from_address, self.network) {
             return Err(BtcApiError::InvalidAddress(format!(
@@ -384,6 +1629,7 @@ impl Bitcoin {
         }
 
         let send_amount = transaction_params.amount;
+        let fee = transaction_params.fee;
 
         let receiver_address =
             Address::from_str(&transaction_params.to_address)?.require_network(self.network)?;
@@ -392,97 +1638,551 @@ impl Bitcoin {
         let sender_address =
             Address::from_str(&transaction_params.from_address)?.require_network(self.network)?;
 
-        //1. Get the Txn inputs based on the UTXOs and the change amount
-        let (inputs, used_utxos, change_amount) = self
-            .get_input_txns_utxos_change_amount(transaction_params)
+        let mut time_locks = TimeLocks::parse(
+            transaction_params.lock_time,
+            &transaction_params.relative_lock_times,
+            &transaction_params.include_utxos,
+        )?;
+
+        let txout_op_return = transaction_params
+            .op_return
+            .as_ref()
+            .map(op_return_output)
+            .transpose()?;
+
+        // Lock times are checked against the tip, fetched before any UTXO gets locked
+        let tip_block = match time_locks.is_empty() {
+            true => None,
+            false => {
+                time_locks.relative_lock_starts =
+                    self.get_relative_lock_starts(&time_locks).await?;
+                Some(self.get_tip_block().await?)
+            }
+        };
+
+        //1. Get the Txn inputs based on the UTXOs, the change amount and the time lock info
+        let (inputs, used_utxos, change_amount, time_lock) = self
+            .get_input_txns_utxos_change_amount(transaction_params, &time_locks, tip_block.as_ref())
             .await?;
 
         debug!("Inputs: {:#?}", inputs);
         debug!("Change amount: {}", change_amount);
 
         let txout_receiver = TxOut {
-            value: Amount::from_sat(send_amount),
+            value: send_amount,
             script_pubkey: receiver_address.script_pubkey(),
         };
 
         let txout_change = TxOut {
-            value: Amount::from_sat(change_amount),
+            value: change_amount,
             script_pubkey: sender_address.script_pubkey(),
         };
 
+        let mut outputs = vec![txout_receiver, txout_change];
+        outputs.extend(txout_op_return);
+
         // Create the unsigned transaction
         debug!("Creating unsigned transaction");
 
+        let txn = Transaction {
+            version: Version::TWO,
+            lock_time: time_locks.get_lock_time(),
+            input: inputs,
+            output: outputs,
+        };
+
+        // Calculate weight & vBytes
+        let vbytes = u64::from(txn.weight()).div_ceil(4);
+
+        // The signed size accounts for the witnesses and the OP_RETURN output
+        let fee_rate = sender_address
+            .address_type()
+            .and_then(|address_type| {
+                estimate_txn_vsize(address_type, txn.input.len(), &txn.output).ok()
+            })
+            .map(|vsize| fee.to_sat() as f64 / vsize as f64);
+
+        info!("Unsigned transaction created: {:#?}", txn);
+        Ok(CreateTransactionResponseData {
+            unsigned_raw_txn: txn,
+            used_utxos,
+            txn_size: vbytes,
+            amount: send_amount,
+            fee,
+            change_amount,
+            fee_rate,
+            utxo_lock_ttl_secs: self.utxo_locks.ttl().as_secs(),
+            time_lock,
+            btc: None,
+        })
+    }
+
+    // Quotes the fee of the transaction createTransaction would build at each network fee
+    // rate. UTXOs are selected like createTransaction does, but nothing is built or locked.
+    async fn estimate_transaction_fee(
+        &self,
+        transaction_params: EstimateTransactionFeeParams,
+    ) -> Result<EstimateTransactionFeeResponseData, BtcApiError> {
+        if !is_valid_bitcoin_address(&transaction_params.from_address, self.network) {
+            return Err(BtcApiError::InvalidAddress(format!(
+                "Invalid from address: {} on network: {}",
+                transaction_params.from_address, self.network
+            )));
+        }
+
+        if !is_valid_bitcoin_address(&transaction_params.to_address, self.network) {
+            return Err(BtcApiError::InvalidAddress(format!(
+                "Invalid to address: {} on network: {}",
+                transaction_params.to_address, self.network
+            )));
+        }
+
+        let receiver_address =
+            Address::from_str(&transaction_params.to_address)?.require_network(self.network)?;
+        let sender_address =
+            Address::from_str(&transaction_params.from_address)?.require_network(self.network)?;
+        let input_type = sender_address.address_type().ok_or_else(|| {
+            BtcApiError::UnsupportedAddressType(transaction_params.from_address.clone())
+        })?;
+
+        let coin_control = CoinControl::parse(
+            &transaction_params.include_utxos,
+            &transaction_params.exclude_utxos,
+        )?;
+
+        // Same outputs as createTransaction, only their scripts matter for the size
+        let mut outputs = vec![
+            TxOut {
+                value: transaction_params.amount,
+                script_pubkey: receiver_address.script_pubkey(),
+            },
+            TxOut {
+                value: Amount::ZERO,
+                script_pubkey: sender_address.script_pubkey(),
+            },
+        ];
+        outputs.extend(
+            transaction_params
+                .op_return
+                .as_ref()
+                .map(op_return_output)
+                .transpose()?,
+        );
+
+        let utxos = self
+            .find_spendable_utxos(transaction_params.from_address.clone())
+            .await?;
+        let network_fee = self.get_network_fee().await?;
+        let locked = self.utxo_locks.locked_outpoints();
+
+        let quote = |fee_rate: f64| -> Result<TransactionFeeQuote, BtcApiError> {
+            match select_utxos_for_fee_rate(
+                &utxos,
+                transaction_params.amount.to_sat(),
+                fee_rate,
+                &coin_control,
+                &locked,
+                input_type,
+                &outputs,
+            ) {
+                Ok((used_utxos, vsize, fee)) => Ok(TransactionFeeQuote {
+                    fee_rate,
+                    fee: Some(fee),
+                    vsize: Some(vsize),
+                    input_count: Some(used_utxos.len()),
+                    missing_amount: None,
+                }),
+                Err(BtcApiError::InsufficientFunds(missing_amount)) => Ok(TransactionFeeQuote {
+                    fee_rate,
+                    fee: None,
+                    vsize: None,
+                    input_count: None,
+                    missing_amount: Some(missing_amount),
+                }),
+                Err(err) => Err(err),
+            }
+        };
+
+        Ok(EstimateTransactionFeeResponseData {
+            fastest_fee: quote(network_fee.fastest_fee)?,
+            half_hour_fee: quote(network_fee.half_hour_fee)?,
+            hour_fee: quote(network_fee.hour_fee)?,
+            economy_fee: quote(network_fee.economy_fee)?,
+            minimum_fee: quote(network_fee.minimum_fee)?,
+            fee_fallback: network_fee.fallback,
+        })
+    }
+
+    async fn consolidate_transaction(
+        &self,
+        consolidation: ConsolidateTransactionParams,
+    ) -> Result<ConsolidateTransactionResponseData, BtcApiError> {
+        // Validate the address
+        if !is_valid_bitcoin_address(&consolidation.address, self.network) {
+            return Err(BtcApiError::InvalidAddress(format!(
+                "Invalid address: {} on network: {}",
+                consolidation.address, self.network
+            )));
+        }
+
+        //This is both the source and the destination of the funds
+        let address = Address::from_str(&consolidation.address)?.require_network(self.network)?;
+
+        let address_type = address
+            .address_type()
+            .ok_or_else(|| BtcApiError::UnsupportedAddressType(consolidation.address.clone()))?;
+
+        let utxos = self.fetch_utxos(&consolidation.address).await?;
+
+        //1. Pick and lock the smallest confirmed and unlocked UTXOs below the threshold
+        let utxos = self.utxo_locks.lock_selection(|locked| {
+            let mut utxos = utxos
+                .into_iter()
+                .filter(|utxo| {
+                    utxo.is_confirmed()
+                        && utxo.value < consolidation.value_threshold.to_sat()
+                        && utxo
+                            .get_outpoint()
+                            .is_ok_and(|outpoint| !locked.contains(&outpoint))
+                })
+                .collect::<Vec<BlockstreamUtxo>>();
+
+            utxos.sort_by_key(|utxo| utxo.value);
+            utxos.truncate(consolidation.max_inputs);
+
+            if utxos.len() < 2 {
+                return Err(BtcApiError::NothingToConsolidate(format!(
+                    "Found {} unlocked confirmed UTXOs below {} for address: {}",
+                    utxos.len(),
+                    consolidation.value_threshold,
+                    consolidation.address
+                )));
+            }
+
+            let outpoints = utxos
+                .iter()
+                .map(BlockstreamUtxo::get_outpoint)
+                .collect::<Result<Vec<OutPoint>, BtcApiError>>()?;
+
+            Ok((utxos, outpoints))
+        })?;
+
+        let outpoints = utxos
+            .iter()
+            .map(BlockstreamUtxo::get_outpoint)
+            .collect::<Result<Vec<OutPoint>, BtcApiError>>()?;
+
+        // Do not keep the UTXOs reserved if the transaction can not be built
+        let consolidation_txn = self
+            .build_consolidation_transaction(
+                address,
+                address_type,
+                utxos,
+                consolidation.max_fee_rate,
+            )
+            .await;
+
+        if consolidation_txn.is_err() {
+            self.utxo_locks.release(&outpoints);
+        }
+
+        consolidation_txn
+    }
+
+    async fn build_consolidation_transaction(
+        &self,
+        address: Address,
+        address_type: AddressType,
+        utxos: Vec<BlockstreamUtxo>,
+        max_fee_rate: Option<u64>,
+    ) -> Result<ConsolidateTransactionResponseData, BtcApiError> {
+        //2. Pay the economy fee rate of the network, capped at max_fee_rate
+        let network_fee_rate = self.get_network_fee().await?.economy_fee.ceil().max(1.0) as u64;
+        let fee_rate = match max_fee_rate {
+            Some(max_fee_rate) => network_fee_rate.min(max_fee_rate),
+            None => network_fee_rate,
+        };
+
+        let mut txout_consolidated = TxOut {
+            value: Amount::ZERO,
+            script_pubkey: address.script_pubkey(),
+        };
+
+        let txn_size = estimate_txn_vsize(
+            address_type,
+            utxos.len(),
+            std::slice::from_ref(&txout_consolidated),
+        )?;
+        let fee = txn_size
+            .checked_mul(fee_rate)
+            .map(Amount::from_sat)
+            .ok_or_else(|| {
+                BtcApiError::InvalidFee(format!(
+                    "Fee rate {} sat/vB for {} vB overflows",
+                    fee_rate, txn_size
+                ))
+            })?;
+        let total_utxo_value = sum_utxo_values(&utxos)?;
+
+        //3. The consolidated output has to be worth more than the fee and the dust limit
+        let min_output_amount = txout_consolidated.script_pubkey.minimal_non_dust();
+        let required_amount = fee.checked_add(min_output_amount).unwrap_or(Amount::MAX);
+        if total_utxo_value < required_amount {
+            return Err(BtcApiError::InsufficientFunds(
+                (required_amount - total_utxo_value).to_sat(),
+            ));
+        }
+
+        let output_amount = total_utxo_value - fee;
+        txout_consolidated.value = output_amount;
+
+        let inputs = utxos
+            .iter()
+            .map(|utxo| {
+                Ok(TxIn {
+                    previous_output: OutPoint::new(Txid::from_str(&utxo.txid)?, utxo.vout),
+                    script_sig: Script::new().into(),
+                    //Should be 0xFFFFFFFF (ignored)
+                    sequence: bitcoin::Sequence(0xFFFFFFFF),
+                    witness: Witness::new(),
+                })
+            })
+            .collect::<Result<Vec<TxIn>, BtcApiError>>()?;
+
         let txn = Transaction {
             version: Version::TWO,
             lock_time: LockTime::ZERO,
             input: inputs,
-            output: vec![txout_receiver, txout_change],
+            output: vec![txout_consolidated],
         };
 
-        info!("Unsigned transaction created: {:#?}", txn);
-        Ok((txn, used_utxos))
+        info!("Unsigned consolidation transaction created: {:#?}", txn);
+
+        Ok(ConsolidateTransactionResponseData {
+            unsigned_raw_txn: txn,
+            used_utxos: utxos,
+            txn_size,
+            fee_rate,
+            fee,
+            output_amount,
+            utxo_lock_ttl_secs: self.utxo_locks.ttl().as_secs(),
+            btc: None,
+        })
+    }
+
+    // BIP68 time based relative locks start at the median time past of the block before the one
+    // which confirmed the UTXO. Unconfirmed UTXOs have no start yet.
+    async fn get_relative_lock_starts(
+        &self,
+        time_locks: &TimeLocks,
+    ) -> Result<HashMap<OutPoint, u64>, BtcApiError> {
+        let lock_starts = stream::iter(time_locks.time_based_outpoints())
+            .map(|outpoint| async move {
+                let status = self.get_txn_status(&outpoint.txid.to_string()).await?;
+
+                let lock_start = match status.block_height {
+                    Some(block_height) if status.confirmed => {
+                        let previous_height = block_height.saturating_sub(1).to_string();
+                        Some(
+                            self.get_blockstream_block(&previous_height)
+                                .await?
+                                .mediantime,
+                        )
+                    }
+                    _ => None,
+                };
+
+                Ok::<_, BtcApiError>(lock_start.map(|lock_start| (outpoint, lock_start)))
+            })
+            .buffered(self.batch_requests.max_concurrency.max(1))
+            .try_collect::<Vec<Option<(OutPoint, u64)>>>()
+            .await?;
+
+        Ok(lock_starts.into_iter().flatten().collect())
     }
 
     async fn get_input_txns_utxos_change_amount(
         &self,
         transaction_params: CreateTransactionParams,
-    ) -> Result<(Vec<TxIn>, Vec<BlockstreamUtxo>, u64), BtcApiError> {
-        let total_expenditure = transaction_params.amount + transaction_params.fee;
-        let mut total_utxo_value = 0;
-        let mut inputs = vec![];
-        let mut used_utxos = vec![];
+        time_locks: &TimeLocks,
+        tip_block: Option<&BlockstreamBlock>,
+    ) -> Result<
+        (
+            Vec<TxIn>,
+            Vec<BlockstreamUtxo>,
+            Amount,
+            Option<TimeLockInfo>,
+        ),
+        BtcApiError,
+    > {
+        let total_expenditure = transaction_params
+            .amount
+            .checked_add(transaction_params.fee)
+            .ok_or_else(|| {
+                BtcApiError::InvalidFee(format!(
+                    "Amount {} plus fee {} overflows",
+                    transaction_params.amount, transaction_params.fee
+                ))
+            })?;
+
+        let coin_control = CoinControl::parse(
+            &transaction_params.include_utxos,
+            &transaction_params.exclude_utxos,
+        )?;
 
         //1. Get the utxos for the from address
-        let mut utxos = self
+        let utxos = self
             .find_spendable_utxos(transaction_params.from_address.clone())
             .await?;
 
-        //2. Sort the UTXOs by value in ascending order, this is to get the smallest utxos first so transaction is split up as much as possible
-        utxos.sort_by_key(|utxo| utxo.value);
+        //2. Select and lock the UTXOs so that concurrent calls can not select them again
+        let (used_utxos, outpoints, change_amount, time_lock) = self.lock_transaction_utxos(
+            utxos,
+            total_expenditure,
+            &coin_control,
+            time_locks,
+            tip_block,
+            transaction_params.allow_non_final,
+        )?;
+
+        let mut inputs = vec![];
+        for (utxo, previous_output) in used_utxos.iter().zip(outpoints) {
+            inputs.push(TxIn {
+                previous_output,
+                script_sig: Script::new().into(),
+                //0xFFFFFFFF (final) unless a lock time is requested
+                sequence: time_locks.get_sequence(&previous_output),
+                witness: Witness::new(),
+            });
+
+            debug!("Added UTXO: {:#?}", utxo);
+        }
 
-        for utxo in utxos {
-            if utxo.is_confirmed() && total_utxo_value < total_expenditure {
-                let txid = Txid::from_str(&utxo.get_txid())?;
-                let outpoint = OutPoint::new(txid, utxo.get_vout());
+        Ok((inputs, used_utxos, change_amount, time_lock))
+    }
 
-                inputs.push(TxIn {
-                    previous_output: outpoint,
-                    script_sig: Script::new().into(),
-                    //Should be 0xFFFFFFFF (ignored)
-                    sequence: bitcoin::Sequence(0xFFFFFFFF),
-                    witness: Witness::new(),
-                });
+    // Everything which can fail once the UTXOs are selected is checked before they are locked,
+    // so a transaction which can not be built leaves no UTXO locked
+    fn lock_transaction_utxos(
+        &self,
+        utxos: Vec<BlockstreamUtxo>,
+        total_expenditure: Amount,
+        coin_control: &CoinControl,
+        time_locks: &TimeLocks,
+        tip_block: Option<&BlockstreamBlock>,
+        allow_non_final: bool,
+    ) -> Result<LockedTransactionUtxos, BtcApiError> {
+        self.utxo_locks
+            .lock_selection(|locked| -> Result<_, BtcApiError> {
+                let used_utxos =
+                    select_utxos(utxos, total_expenditure.to_sat(), coin_control, locked)?;
+
+                let outpoints = used_utxos
+                    .iter()
+                    .map(BlockstreamUtxo::get_outpoint)
+                    .collect::<Result<Vec<OutPoint>, BtcApiError>>()?;
+
+                let total_utxo_value = sum_utxo_values(&used_utxos)?;
+                let change_amount =
+                    total_utxo_value
+                        .checked_sub(total_expenditure)
+                        .ok_or_else(|| {
+                            BtcApiError::InsufficientFunds(
+                                (total_expenditure - total_utxo_value).to_sat(),
+                            )
+                        })?;
+
+                let time_lock = time_locks.check(&used_utxos, tip_block, allow_non_final)?;
+
+                Ok((
+                    (used_utxos, outpoints.clone(), change_amount, time_lock),
+                    outpoints,
+                ))
+            })
+    }
+
+    async fn decode_transaction(
+        &self,
+        raw_txn: String,
+    ) -> Result<DecodeTransactionResponseData, BtcApiError> {
+        let transaction = deserialize_hex::<Transaction>(raw_txn.trim())?;
 
-                debug!("Added UTXO: {:#?}", utxo);
-                total_utxo_value += utxo.value;
-                used_utxos.push(utxo);
+        // The fee can only be computed when all the spent outputs are known
+        let prevouts = match self.fetch_prevouts(&transaction).await {
+            Ok(prevouts) => Some(prevouts),
+            Err(err) => {
+                debug!("Unable to fetch prevouts: {}", err);
+                None
             }
-        }
+        };
 
-        if total_expenditure > total_utxo_value {
-            return Err(BtcApiError::InsufficientFunds(
-                total_expenditure - total_utxo_value,
-            ));
-        }
+        decode_transaction(&transaction, self.network, prevouts.as_deref())
+    }
 
-        Ok((
-            inputs.to_vec(),
-            used_utxos,
-            (total_utxo_value - total_expenditure),
-        ))
+    // Fetches the outputs spent by the inputs of the transaction, in the same order, at most
+    // max_concurrency at a time
+    async fn fetch_prevouts(&self, transaction: &Transaction) -> Result<Vec<TxOut>, BtcApiError> {
+        let outpoints = transaction
+            .input
+            .iter()
+            .map(|input| input.previous_output)
+            .collect::<Vec<OutPoint>>();
+
+        stream::iter(outpoints)
+            .map(|outpoint| async move {
+                let url = self.rpc_url.join(&format!("tx/{}/hex", outpoint.txid))?;
+
+                let response = reqwest::get(url).await?;
+                if !response.status().is_success() {
+                    return Err(BtcApiError::UtxoNotFound(outpoint.to_string()));
+                }
+
+                let previous_transaction =
+                    deserialize_hex::<Transaction>(response.text().await?.trim())?;
+
+                previous_transaction
+                    .output
+                    .get(outpoint.vout as usize)
+                    .cloned()
+                    .ok_or_else(|| BtcApiError::UtxoNotFound(outpoint.to_string()))
+            })
+            .buffered(self.batch_requests.max_concurrency.max(1))
+            .try_collect()
+            .await
     }
 
-    async fn find_spendable_utxos(
+    fn release_utxos(
         &self,
-        address: String,
-    ) -> Result<Vec<BlockstreamUtxo>, BtcApiError> {
+        release: ReleaseUtxosParams,
+    ) -> Result<ReleaseUtxosResponseData, BtcApiError> {
+        let outpoints = parse_outpoints(&release.utxos)?;
+
+        let released_utxos = self
+            .utxo_locks
+            .release(&outpoints)
+            .iter()
+            .map(OutPoint::to_string)
+            .collect();
+
+        Ok(ReleaseUtxosResponseData { released_utxos })
+    }
+
+    async fn fetch_utxos(&self, address: &str) -> Result<Vec<BlockstreamUtxo>, BtcApiError> {
         //todo: Do URL parsing here
         let url = self.rpc_url.join(&format!("address/{}/utxo", address))?;
 
         let blockstream_response = reqwest::get(url).await?.text().await?;
-        let blockstream_utxos =
-            serde_json::from_str::<Vec<BlockstreamUtxo>>(&blockstream_response)?;
+
+        Ok(serde_json::from_str::<Vec<BlockstreamUtxo>>(
+            &blockstream_response,
+        )?)
+    }
+
+    async fn find_spendable_utxos(
+        &self,
+        address: String,
+    ) -> Result<Vec<BlockstreamUtxo>, BtcApiError> {
+        let blockstream_utxos = self.fetch_utxos(&address).await?;
 
         if blockstream_utxos.is_empty() {
             Err(BtcApiError::NoUtxosFound(address))
@@ -539,33 +2239,523 @@ impl Bitcoin {
         signed_txn_hash
     }
 
+    // Decodes the transaction and checks it against the broadcast policy before it is relayed
+    async fn check_broadcast_policy(
+        &self,
+        signed_txn_hash: &str,
+    ) -> Result<(Amount, f64), BtcApiError> {
+        let transaction = deserialize_hex::<Transaction>(signed_txn_hash.trim())?;
+
+        // Prevouts are looked up on the configured network, missing ones mean the transaction
+        // spends outputs of another network or outputs which do not exist
+        let prevouts = self.fetch_prevouts(&transaction).await.map_err(|err| {
+            BtcApiError::BroadcastPolicyViolation(format!(
+                "Unable to find the spent outputs on network {}: {}",
+                self.network, err
+            ))
+        })?;
+
+        let outpoints = transaction
+            .input
+            .iter()
+            .map(|input| input.previous_output)
+            .collect::<Vec<OutPoint>>();
+
+        let outspends = stream::iter(outpoints)
+            .map(|outpoint| async move {
+                let url = self
+                    .rpc_url
+                    .join(&format!("tx/{}/outspend/{}", outpoint.txid, outpoint.vout))?;
+
+                let outspend = reqwest::get(url)
+                    .await?
+                    .json::<BlockstreamOutspend>()
+                    .await?;
+
+                Ok::<_, BtcApiError>((outpoint, outspend))
+            })
+            .buffered(self.batch_requests.max_concurrency.max(1))
+            .try_collect::<Vec<(OutPoint, BlockstreamOutspend)>>()
+            .await?;
+
+        // An input spent by this very transaction is a rebroadcast of it
+        let txid = transaction.compute_txid().to_string();
+        for (outpoint, outspend) in outspends {
+            if outspend.spent && outspend.txid.as_ref() != Some(&txid) {
+                return Err(BtcApiError::BroadcastPolicyViolation(format!(
+                    "Input {} is already spent by {}",
+                    outpoint,
+                    outspend.txid.unwrap_or("Unknown".to_string())
+                )));
+            }
+        }
+
+        let (fee, fee_rate) =
+            check_broadcast_policy(&transaction, &prevouts, &self.broadcast_policy)?;
+
+        debug!(
+            "Transaction passed the broadcast policy with fee {} at {:.2} sat/vB",
+            fee, fee_rate
+        );
+
+        Ok((fee, fee_rate))
+    }
+
+    fn parse_payment_uri(&self, uri: String) -> Result<PaymentUriData, BtcApiError> {
+        let payment_uri = parse_payment_uri(&uri)?;
+
+        if !is_valid_bitcoin_address(&payment_uri.address, self.network) {
+            return Err(BtcApiError::InvalidAddress(format!(
+                "Invalid payment URI address: {} on network: {}",
+                payment_uri.address, self.network
+            )));
+        }
+
+        Ok(payment_uri)
+    }
+
+    fn create_payment_uri(
+        &self,
+        params: CreatePaymentUriParams,
+    ) -> Result<PaymentUriData, BtcApiError> {
+        if !is_valid_bitcoin_address(&params.address, self.network) {
+            return Err(BtcApiError::InvalidAddress(format!(
+                "Invalid address: {} on network: {}",
+                params.address, self.network
+            )));
+        }
+
+        create_payment_uri(&params)
+    }
+
+    // Only addresses of the service keystore can be signed for
+    fn sign_message(
+        &self,
+        params: SignMessageParams,
+    ) -> Result<SignMessageResponseData, BtcApiError> {
+        if !self.sign_txn {
+            return Err(BtcApiError::MessageSigningError(
+                "Signing is disabled".to_string(),
+            ));
+        }
+
+        let address = Address::from_str(&params.address)?.require_network(self.network)?;
+
+        let secp = Secp256k1::new();
+        let (sk, _) = senders_keys(&secp);
+        let public_key = CompressedPublicKey(sk.public_key(&secp));
+        let keystore_addresses = [
+            Address::p2pkh(public_key, self.network),
+            Address::p2shwpkh(&public_key, self.network),
+            Address::p2wpkh(&public_key, self.network),
+            Address::p2tr(
+                &secp,
+                public_key.0.x_only_public_key().0,
+                None,
+                self.network,
+            ),
+        ];
+
+        if !keystore_addresses.contains(&address) {
+            return Err(BtcApiError::MessageSigningError(format!(
+                "Address {} is not in the keystore",
+                address
+            )));
+        }
+
+        let format = params
+            .format
+            .unwrap_or_else(|| default_signature_format(&address));
+        let signature = sign_message(&sk, &address, &params.message, &format)?;
+
+        Ok(SignMessageResponseData {
+            address: params.address,
+            message: params.message,
+            signature,
+            format,
+        })
+    }
+
+    fn verify_message(
+        &self,
+        params: VerifyMessageParams,
+    ) -> Result<VerifyMessageResponseData, BtcApiError> {
+        let address = Address::from_str(&params.address)?.require_network(self.network)?;
+        let (is_valid, format) =
+            verify_message(&address, &params.message, &params.signature, self.network)?;
+
+        Ok(VerifyMessageResponseData {
+            address: params.address,
+            is_valid,
+            format,
+        })
+    }
+
+    fn release_spent_utxos(&self, transaction: &Transaction) {
+        let outpoints = transaction
+            .input
+            .iter()
+            .map(|input| input.previous_output)
+            .collect::<Vec<OutPoint>>();
+        self.utxo_locks.release(&outpoints);
+    }
+
+    // Submits dependent transactions (e.g. a zero fee parent with its CPFP child) together with
+    // bitcoind submitpackage. Without bitcoind, or when the node does not know submitpackage, the
+    // transactions are broadcasted one by one in the given order.
+    async fn submit_package(
+        &self,
+        signed_txn_hashes: Vec<String>,
+        force: bool,
+    ) -> Result<SubmitPackageResponseData, BtcApiError> {
+        if signed_txn_hashes.is_empty() || signed_txn_hashes.len() > MAX_PACKAGE_COUNT {
+            return Err(BtcApiError::InvalidPackage(format!(
+                "A package has 1 to {} transactions, got {}",
+                MAX_PACKAGE_COUNT,
+                signed_txn_hashes.len()
+            )));
+        }
+
+        let signed_txn_hashes = signed_txn_hashes
+            .iter()
+            .map(|signed_txn_hash| signed_txn_hash.trim().to_string())
+            .collect::<Vec<String>>();
+        let transactions = signed_txn_hashes
+            .iter()
+            .map(|signed_txn_hash| deserialize_hex::<Transaction>(signed_txn_hash))
+            .collect::<Result<Vec<Transaction>, _>>()?;
+
+        if let Some(bitcoind) = self.bitcoind.clone() {
+            if !force {
+                self.check_package_broadcast_policy(&transactions, &signed_txn_hashes)
+                    .await?;
+            }
+
+            let raw_txns = signed_txn_hashes.clone();
+            let package_result = tokio::task::spawn_blocking(move || {
+                bitcoind.call::<BitcoindSubmitPackageResult>(
+                    "submitpackage",
+                    &[serde_json::json!(raw_txns)],
+                )
+            })
+            .await
+            .map_err(|err| BtcApiError::ExternalApiError(err.to_string()))?;
+
+            match package_result {
+                Ok(package_result) => {
+                    info!("submitpackage result: {:?}", package_result);
+                    return Ok(self.get_package_results(&transactions, package_result));
+                }
+                // Method not found, the node predates package relay
+                Err(bitcoincore_rpc::Error::JsonRpc(bitcoincore_rpc::jsonrpc::Error::Rpc(
+                    rpc_error,
+                ))) if rpc_error.code == -32601 => {
+                    info!("bitcoind does not support submitpackage, broadcasting sequentially");
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let mut txn_results: Vec<PackageTxnResult> = vec![];
+        for (transaction, signed_txn_hash) in transactions.iter().zip(signed_txn_hashes) {
+            let txid = transaction.compute_txid().to_string();
+
+            // Children can not be accepted without their parents
+            if txn_results.iter().any(|txn_result| !txn_result.accepted) {
+                txn_results.push(PackageTxnResult {
+                    txid,
+                    accepted: false,
+                    error_msg: Some(
+                        "Skipped, an earlier transaction of the package was rejected".to_string(),
+                    ),
+                    error_code: None,
+                });
+                continue;
+            }
+
+            txn_results.push(
+                match self.broadcast_transaction(signed_txn_hash, force).await {
+                    Ok(_) => PackageTxnResult {
+                        txid,
+                        accepted: true,
+                        error_msg: None,
+                        error_code: None,
+                    },
+                    Err(err) => PackageTxnResult {
+                        txid,
+                        accepted: false,
+                        error_code: err.broadcast_error_code(),
+                        error_msg: Some(err.to_string()),
+                    },
+                },
+            );
+        }
+
+        Ok(SubmitPackageResponseData {
+            submitted_via: "sequential".to_string(),
+            all_accepted: txn_results.iter().all(|txn_result| txn_result.accepted),
+            package_msg: None,
+            txn_results,
+        })
+    }
+
+    // The spent outputs of a transaction spending another transaction of the package can not be
+    // looked up before the package is submitted, such transactions are left to bitcoind. The
+    // others, including the first one, are checked like a single broadcast.
+    async fn check_package_broadcast_policy(
+        &self,
+        transactions: &[Transaction],
+        signed_txn_hashes: &[String],
+    ) -> Result<(), BtcApiError> {
+        let package_txids = transactions
+            .iter()
+            .map(Transaction::compute_txid)
+            .collect::<Vec<Txid>>();
+
+        for (transaction, signed_txn_hash) in transactions.iter().zip(signed_txn_hashes) {
+            let spends_package = transaction
+                .input
+                .iter()
+                .any(|input| package_txids.contains(&input.previous_output.txid));
+            if spends_package {
+                continue;
+            }
+
+            self.check_broadcast_policy(signed_txn_hash)
+                .await
+                .map_err(|err| match err {
+                    BtcApiError::BroadcastPolicyViolation(msg) => {
+                        BtcApiError::BroadcastPolicyViolation(format!(
+                            "{}: {}",
+                            transaction.compute_txid(),
+                            msg
+                        ))
+                    }
+                    err => err,
+                })?;
+        }
+
+        Ok(())
+    }
+
+    // Maps the wtxid keyed submitpackage results back to the submitted transactions
+    fn get_package_results(
+        &self,
+        transactions: &[Transaction],
+        package_result: BitcoindSubmitPackageResult,
+    ) -> SubmitPackageResponseData {
+        let txn_results = transactions
+            .iter()
+            .map(|transaction| {
+                let txid = transaction.compute_txid().to_string();
+                let wtxid = transaction.compute_wtxid().to_string();
+
+                match package_result.tx_results.get(&wtxid) {
+                    Some(tx_result) if tx_result.error.is_none() => {
+                        self.release_spent_utxos(transaction);
+                        PackageTxnResult {
+                            txid,
+                            accepted: true,
+                            error_msg: None,
+                            error_code: None,
+                        }
+                    }
+                    Some(tx_result) => PackageTxnResult {
+                        txid,
+                        accepted: false,
+                        error_msg: tx_result.error.clone(),
+                        error_code: tx_result.error.as_deref().map(classify_reject_reason),
+                    },
+                    None => PackageTxnResult {
+                        txid,
+                        accepted: false,
+                        error_msg: Some(package_result.package_msg.clone()),
+                        error_code: Some(classify_reject_reason(&package_result.package_msg)),
+                    },
+                }
+            })
+            .collect::<Vec<PackageTxnResult>>();
+
+        SubmitPackageResponseData {
+            submitted_via: "bitcoind".to_string(),
+            all_accepted: txn_results.iter().all(|txn_result| txn_result.accepted),
+            package_msg: Some(package_result.package_msg),
+            txn_results,
+        }
+    }
+
+    // Dry run of a broadcast, nothing is relayed. Uses testmempoolaccept when bitcoind is
+    // configured, the local broadcast policy otherwise.
+    async fn test_transaction(
+        &self,
+        signed_txn_hash: String,
+    ) -> Result<TestTransactionResponseData, BtcApiError> {
+        let transaction = deserialize_hex::<Transaction>(signed_txn_hash.trim())?;
+        let txid = transaction.compute_txid().to_string();
+        let vsize = transaction.vsize() as u64;
+
+        let Some(bitcoind) = self.bitcoind.clone() else {
+            return match self.check_broadcast_policy(&signed_txn_hash).await {
+                Ok((fee, fee_rate)) => Ok(TestTransactionResponseData {
+                    txid,
+                    allowed: true,
+                    reject_reason: None,
+                    error_code: None,
+                    vsize,
+                    fee: Some(fee.to_sat()),
+                    fee_rate: Some(fee_rate),
+                    checked_by: "localPolicy".to_string(),
+                }),
+                Err(BtcApiError::BroadcastPolicyViolation(reject_reason)) => {
+                    Ok(TestTransactionResponseData {
+                        txid,
+                        allowed: false,
+                        reject_reason: Some(reject_reason),
+                        error_code: Some(BroadcastErrorCode::PolicyViolation),
+                        vsize,
+                        fee: None,
+                        fee_rate: None,
+                        checked_by: "localPolicy".to_string(),
+                    })
+                }
+                Err(err) => Err(err),
+            };
+        };
+
+        let raw_txn = signed_txn_hash.trim().to_string();
+        let test_result =
+            tokio::task::spawn_blocking(move || bitcoind.test_mempool_accept(&[raw_txn.as_str()]))
+                .await
+                .map_err(|err| BtcApiError::ExternalApiError(err.to_string()))??
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    BtcApiError::ExternalApiError("Empty testmempoolaccept result".to_string())
+                })?;
+
+        let vsize = test_result.vsize.unwrap_or(vsize);
+        let fee = test_result.fees.map(|fees| fees.base.to_sat());
+
+        Ok(TestTransactionResponseData {
+            txid,
+            allowed: test_result.allowed,
+            error_code: test_result
+                .reject_reason
+                .as_deref()
+                .map(classify_reject_reason),
+            reject_reason: test_result.reject_reason,
+            vsize,
+            fee,
+            fee_rate: fee.map(|fee| fee as f64 / vsize as f64),
+            checked_by: "bitcoind".to_string(),
+        })
+    }
+
     async fn broadcast_transaction(
         &self,
         signed_txn_hash: String,
+        force: bool,
     ) -> Result<BroadcastTransactionResponseData, BtcApiError> {
+        let signed_txn_hash = signed_txn_hash.trim().to_string();
+
+        if force {
+            info!("Skipping broadcast policy checks, broadcast is forced");
+        } else {
+            self.check_broadcast_policy(&signed_txn_hash).await?;
+        }
+
         info!("Broadcasting transaction: {}", signed_txn_hash);
 
-        let url = self.rpc_url.join("tx")?;
+        let transaction = deserialize_hex::<Transaction>(&signed_txn_hash)?;
+        let txid = transaction.compute_txid();
 
         let client = Client::new();
-        let response = client.post(url).body(signed_txn_hash).send().await?;
+        let broadcasts = self.broadcast_providers.iter().map(|provider| {
+            let client = &client;
+            let signed_txn_hash = &signed_txn_hash;
+            async move {
+                (
+                    provider.name(),
+                    provider.broadcast(client, signed_txn_hash).await,
+                )
+            }
+        });
+
+        let provider_results = join_all(broadcasts)
+            .await
+            .into_iter()
+            .map(|(provider, broadcast_result)| {
+                let provider_result = match broadcast_result {
+                    Ok(returned_txid) if returned_txid == txid => ProviderBroadcastResult {
+                        provider,
+                        accepted: true,
+                        error_msg: None,
+                        error_code: None,
+                    },
+                    Ok(returned_txid) => ProviderBroadcastResult {
+                        provider,
+                        accepted: false,
+                        error_msg: Some(format!(
+                            "Returned txid {} does not match the transaction's txid {}",
+                            returned_txid, txid
+                        )),
+                        error_code: Some(BroadcastErrorCode::Unknown),
+                    },
+                    Err(err) => {
+                        let error_code = err.broadcast_error_code();
+                        ProviderBroadcastResult {
+                            provider,
+                            accepted: error_code == Some(BroadcastErrorCode::AlreadyInMempool),
+                            error_msg: Some(err.to_string()),
+                            error_code,
+                        }
+                    }
+                };
+
+                info!("Broadcast result: {:?}", provider_result);
+                provider_result
+            })
+            .collect::<Vec<ProviderBroadcastResult>>();
+
+        if !provider_results.iter().any(|result| result.accepted) {
+            let error_msg = provider_results
+                .iter()
+                .map(|result| {
+                    format!(
+                        "{}: {}",
+                        result.provider,
+                        result.error_msg.clone().unwrap_or_default()
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("; ");
+
+            // The first classified reason tells the caller how to react
+            let error_code = provider_results.iter().find_map(|result| {
+                result
+                    .error_code
+                    .clone()
+                    .filter(|code| *code != BroadcastErrorCode::Unknown)
+            });
+
+            return Err(match error_code {
+                Some(code) => BtcApiError::TransactionRejected(code, error_msg),
+                None => BtcApiError::InvalidBroadcastResponse(error_msg),
+            });
+        }
 
-        let response_text = response.text().await?;
+        info!("✅ Transaction broadcasted: {}", txid);
 
-        //Check if the response text is a hash
-        if self.bitcoin_txid_regex.is_match(&response_text) {
-            info!("✅ Transaction broadcast result: {}", response_text);
+        // Transaction broadcasted successfully, its inputs are spent now
+        self.release_spent_utxos(&transaction);
 
-            //Valid txid, transaction broadcasted successfully
-            let explorer_url = self.explorer_url.join(&format!("tx/{}", response_text))?;
+        let explorer_url = self.explorer_url.join(&format!("tx/{}", txid))?;
 
-            Ok(BroadcastTransactionResponseData {
-                txn_hash: response_text,
-                txn_hash_url: explorer_url.to_string(),
-            })
-        } else {
-            Err(BtcApiError::InvalidBroadcastResponse(response_text))
-        }
+        Ok(BroadcastTransactionResponseData {
+            txn_hash: txid.to_string(),
+            txn_hash_url: explorer_url.to_string(),
+            provider_results,
+        })
     }
 }
 
@@ -606,6 +2796,476 @@ async fn test_get_raw_transaction() {
     // );
 }
 
+#[test]
+fn test_with_broadcast_providers() {
+    let bitcoin = Bitcoin::new(
+        "https://blockstream.info/testnet/api/",
+        &ChainVariant::Testnet,
+        false,
+    )
+    .unwrap();
+
+    // rpc_url is the only provider by default
+    assert_eq!(bitcoin.broadcast_providers.len(), 1);
+
+    let providers: Vec<BroadcastProviderConfig> = serde_json::from_str(
+        r#"[
+            { "type": "esplora", "url": "https://blockstream.info/testnet/api/" },
+            { "type": "esplora", "url": "https://mempool.space/testnet/api/" },
+            { "type": "bitcoind" }
+        ]"#,
+    )
+    .unwrap();
+
+    // The bitcoind provider needs a bitcoind connection
+    assert!(bitcoin
+        .clone()
+        .with_broadcast_providers(&providers)
+        .is_err());
+
+    let bitcoin = bitcoin.with_broadcast_providers(&providers[..2]).unwrap();
+    assert_eq!(
+        bitcoin
+            .broadcast_providers
+            .iter()
+            .map(BroadcastProvider::name)
+            .collect::<Vec<String>>(),
+        vec!["esplora(blockstream.info)", "esplora(mempool.space)"]
+    );
+}
+
+#[tokio::test]
+async fn test_get_fiat_price() {
+    let mut price_feed = price_feed::MockPriceFeed::new();
+    price_feed
+        .expect_get_price()
+        .withf(|currency, timestamp| currency == "USD" && *timestamp == Some(1738199336))
+        .times(1)
+        .returning(|currency, _| {
+            let currency = currency.to_string();
+            Box::pin(async move {
+                Ok(FiatPrice {
+                    currency,
+                    price: 104500.0,
+                    time: 1738195200,
+                })
+            })
+        });
+
+    let mut bitcoin = Bitcoin::new(
+        "https://blockstream.info/testnet/api/",
+        &ChainVariant::Testnet,
+        false,
+    )
+    .unwrap();
+    bitcoin.price_feed = Arc::new(price_feed);
+
+    let price = bitcoin
+        .get_fiat_price("USD", Some(1738199336))
+        .await
+        .unwrap();
+    assert_eq!(price.price, 104500.0);
+
+    // Rejected before reaching the price feed
+    assert!(matches!(
+        bitcoin.get_fiat_price("US D", None).await,
+        Err(BtcApiError::PriceFeedError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_get_wallet_balances_batch_size() {
+    let bitcoin = Bitcoin::new(
+        "https://blockstream.info/testnet/api/",
+        &ChainVariant::Testnet,
+        false,
+    )
+    .unwrap()
+    .with_batch_requests(BatchRequestsConfig {
+        max_batch_size: 2,
+        max_concurrency: 2,
+    });
+
+    // Rejected before any address is fetched
+    for wallet_addresses in [vec![], vec!["invalid".to_string(); 3]] {
+        let params = WalletBalancesParams {
+            wallet_addresses,
+            currency: None,
+            include_btc: false,
+        };
+        assert!(matches!(
+            bitcoin.get_wallet_balances(params).await,
+            Err(BtcApiError::InvalidBatch(_))
+        ));
+    }
+
+    // Invalid addresses fail on their own without a request to the provider
+    let params = WalletBalancesParams {
+        wallet_addresses: vec![
+            "invalid".to_string(),
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string(),
+        ],
+        currency: None,
+        include_btc: false,
+    };
+    let balances = bitcoin.get_wallet_balances(params).await.unwrap().balances;
+    assert_eq!(balances.len(), 2);
+    assert!(balances.iter().all(|balance| balance.is_error));
+    assert_eq!(balances[0].wallet_address, "invalid");
+}
+
+#[tokio::test]
+async fn test_validate_transaction_hashes_batch_size() {
+    let bitcoin = Bitcoin::new(
+        "https://blockstream.info/testnet/api/",
+        &ChainVariant::Testnet,
+        false,
+    )
+    .unwrap()
+    .with_batch_requests(BatchRequestsConfig {
+        max_batch_size: 2,
+        max_concurrency: 2,
+    });
+
+    // Rejected before the tip height or any transaction is fetched
+    for transaction_hashes in [vec![], vec!["abcd".to_string(); 3]] {
+        let params = ValidateTransactionHashesParams {
+            transaction_hashes,
+            currency: None,
+            include_btc: false,
+        };
+        assert!(matches!(
+            bitcoin.validate_transaction_hashes(params).await,
+            Err(BtcApiError::InvalidBatch(_))
+        ));
+    }
+}
+
+// Serves the given routes on a local port, returns the base URL of the API
+#[cfg(test)]
+async fn spawn_mock_api(routes: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, routes).await });
+
+    format!("http://{}/", address)
+}
+
+#[tokio::test]
+async fn test_get_txn_block_status_testnet() {
+    use axum::{routing::get, Json};
+
+    let confirmed_txn_hash = "6f3a1ff2a4a4b5e4c6d9c5d8a3f6e9b0c1d2e3f4a5b6c7d8e9f0a1b2c3d4e5f6";
+    let esplora = spawn_mock_api(axum::Router::new().route(
+        &format!("/tx/{}/status", confirmed_txn_hash),
+        get(|| async {
+            Json(serde_json::json!({
+                "confirmed": true,
+                "block_height": 3659267,
+                "block_hash": "000000000000001b5e5ebb1d6d7c8ef1c1b4e0e0b0d4fbd4d4f9e4f0b1c2d3e4",
+                "block_time": 1738199336
+            }))
+        }),
+    ))
+    .await;
+
+    // Counted on the configured network, not only on mainnet
+    let bitcoin = Bitcoin::new(&esplora, &ChainVariant::Testnet, false).unwrap();
+    let block_status = bitcoin
+        .get_txn_block_status(confirmed_txn_hash)
+        .await
+        .unwrap();
+    assert_eq!(block_status.get_confirmations(3659272), 6);
+    assert_eq!(block_status.block_time, Some(1738199336));
+
+    // Unknown to the provider
+    assert!(bitcoin
+        .get_txn_block_status(&"0".repeat(64))
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_get_address_utxos_without_tip_height() {
+    use axum::{routing::get, Json};
+
+    let address = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    // blocks/tip/height is not served
+    let esplora = spawn_mock_api(axum::Router::new().route(
+        &format!("/address/{}/utxo", address),
+        get(|| async {
+            Json(serde_json::json!([
+                {
+                    "txid": "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2",
+                    "vout": 0,
+                    "status": { "confirmed": true, "block_height": 3659267, "block_time": 1738199336 },
+                    "value": 30000
+                },
+                {
+                    "txid": "d2c5a5b1e0c3f4a6b7d8e9f0a1b2c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0",
+                    "vout": 1,
+                    "status": { "confirmed": false },
+                    "value": 20000
+                }
+            ]))
+        }),
+    ))
+    .await;
+
+    let bitcoin = Bitcoin::new(&esplora, &ChainVariant::Testnet, false).unwrap();
+    let filters = AddressUtxosParams {
+        min_confirmations: None,
+        min_value: None,
+        include_btc: false,
+    };
+    let utxos = bitcoin
+        .get_address_utxos(address.to_string(), filters)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        utxos
+            .utxos
+            .iter()
+            .map(|utxo| utxo.confirmations)
+            .collect::<Vec<Option<u64>>>(),
+        vec![None, Some(0)]
+    );
+    assert_eq!(utxos.total_value, Amount::from_sat(50000));
+}
+
+// A previous transaction and a transaction spending its only output with a 1000 sat fee
+#[cfg(test)]
+fn spending_transactions() -> (Transaction, Transaction) {
+    use bitcoin::{hashes::Hash, WPubkeyHash};
+
+    let previous_txn = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn::default()],
+        output: vec![TxOut {
+            value: Amount::from_sat(100_000),
+            script_pubkey: ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array([1; 20])),
+        }],
+    };
+
+    let txn = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(previous_txn.compute_txid(), 0),
+            ..Default::default()
+        }],
+        output: vec![TxOut {
+            value: Amount::from_sat(99_000),
+            script_pubkey: ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array([2; 20])),
+        }],
+    };
+
+    (previous_txn, txn)
+}
+
+#[tokio::test]
+async fn test_check_broadcast_policy_outspends() {
+    use axum::{routing::get, Json};
+
+    let (previous_txn, txn) = spending_transactions();
+    let previous_txid = previous_txn.compute_txid();
+
+    let esplora = |spending_txid: String| {
+        let previous_txn_hex = serialize_hex(&previous_txn);
+        spawn_mock_api(
+            axum::Router::new()
+                .route(
+                    &format!("/tx/{}/hex", previous_txid),
+                    get(|| async move { previous_txn_hex }),
+                )
+                .route(
+                    &format!("/tx/{}/outspend/0", previous_txid),
+                    get(|| async move {
+                        Json(serde_json::json!({ "spent": true, "txid": spending_txid }))
+                    }),
+                ),
+        )
+    };
+
+    // Rebroadcasting a transaction already in the mempool is not a violation
+    let bitcoin = Bitcoin::new(
+        &esplora(txn.compute_txid().to_string()).await,
+        &ChainVariant::Testnet,
+        false,
+    )
+    .unwrap();
+    let (fee, _) = bitcoin
+        .check_broadcast_policy(&serialize_hex(&txn))
+        .await
+        .unwrap();
+    assert_eq!(fee, Amount::from_sat(1000));
+
+    // Spent by another transaction
+    let bitcoin = Bitcoin::new(
+        &esplora("0".repeat(64)).await,
+        &ChainVariant::Testnet,
+        false,
+    )
+    .unwrap();
+    assert!(matches!(
+        bitcoin.check_broadcast_policy(&serialize_hex(&txn)).await,
+        Err(BtcApiError::BroadcastPolicyViolation(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_submit_package_bitcoind_policy() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::{
+        routing::{get, post},
+        Json,
+    };
+
+    let (previous_txn, txn) = spending_transactions();
+    let previous_txid = previous_txn.compute_txid();
+    let previous_txn_hex = serialize_hex(&previous_txn);
+    let wtxid = txn.compute_wtxid().to_string();
+
+    // The only output of the previous transaction is already spent by another transaction
+    let esplora = spawn_mock_api(
+        axum::Router::new()
+            .route(
+                &format!("/tx/{}/hex", previous_txid),
+                get(|| async move { previous_txn_hex }),
+            )
+            .route(
+                &format!("/tx/{}/outspend/0", previous_txid),
+                get(|| async {
+                    Json(serde_json::json!({ "spent": true, "txid": "0".repeat(64) }))
+                }),
+            ),
+    )
+    .await;
+
+    let submit_package_calls = Arc::new(AtomicUsize::new(0));
+    let calls = Arc::clone(&submit_package_calls);
+    let bitcoind = spawn_mock_api(axum::Router::new().route(
+        "/",
+        post(move |Json(request): Json<serde_json::Value>| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                assert_eq!(request["method"], "submitpackage");
+                Json(serde_json::json!({
+                    "result": {
+                        "package_msg": "success",
+                        "tx-results": { wtxid: { "txid": "", "error": null } }
+                    },
+                    "error": null,
+                    "id": request["id"]
+                }))
+            }
+        }),
+    ))
+    .await;
+
+    let bitcoin = Bitcoin::new(&esplora, &ChainVariant::Testnet, false)
+        .unwrap()
+        .with_bitcoind(Some(&BitcoindConfig {
+            url: bitcoind,
+            user: "user".to_string(),
+            password: "password".to_string(),
+        }))
+        .unwrap();
+
+    // Checked like a single broadcast before reaching bitcoind
+    assert!(matches!(
+        bitcoin
+            .submit_package(vec![serialize_hex(&txn)], false)
+            .await,
+        Err(BtcApiError::BroadcastPolicyViolation(_))
+    ));
+    assert_eq!(submit_package_calls.load(Ordering::SeqCst), 0);
+
+    let package = bitcoin
+        .submit_package(vec![serialize_hex(&txn)], true)
+        .await
+        .unwrap();
+    assert!(package.all_accepted);
+    assert_eq!(submit_package_calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_lock_transaction_utxos_time_lock_failure() {
+    let bitcoin = Bitcoin::new(
+        "https://blockstream.info/testnet/api/",
+        &ChainVariant::Testnet,
+        false,
+    )
+    .unwrap();
+
+    let outpoint = "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2:0";
+    let utxos: Vec<BlockstreamUtxo> = serde_json::from_str(
+        r#"[{
+            "txid": "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2",
+            "vout": 0,
+            "status": { "confirmed": true, "block_height": 3659267, "block_time": 1738199336 },
+            "value": 30000
+        }]"#,
+    )
+    .unwrap();
+    let tip_block: BlockstreamBlock = serde_json::from_str(
+        r#"{
+            "id": "000000000000000b1b2d4e1e8a8f4b3c6c1e2c0f4e0b7a1d9c3e5f7a9b1c3d5e",
+            "height": 3659300,
+            "timestamp": 1738210000,
+            "mediantime": 1738200000,
+            "tx_count": 1,
+            "size": 285,
+            "weight": 1140,
+            "previousblockhash": null
+        }"#,
+    )
+    .unwrap();
+
+    let coin_control = CoinControl::parse(&[outpoint.to_string()], &[]).unwrap();
+    let relative_lock_times = HashMap::from([(
+        outpoint.to_string(),
+        crate::models::RelativeLockTime::Blocks(144),
+    )]);
+    let time_locks = TimeLocks::parse(None, &relative_lock_times, &[outpoint.to_string()]).unwrap();
+
+    // The relative lock time is not satisfied at the tip, nothing gets locked
+    let result = bitcoin.lock_transaction_utxos(
+        utxos.clone(),
+        Amount::from_sat(20000),
+        &coin_control,
+        &time_locks,
+        Some(&tip_block),
+        false,
+    );
+    assert!(matches!(result, Err(BtcApiError::InvalidLockTime(_))));
+    assert!(bitcoin.utxo_locks.locked_outpoints().is_empty());
+
+    // The same UTXO can be selected again right away
+    let (used_utxos, outpoints, change_amount, time_lock) = bitcoin
+        .lock_transaction_utxos(
+            utxos,
+            Amount::from_sat(20000),
+            &coin_control,
+            &time_locks,
+            Some(&tip_block),
+            true,
+        )
+        .unwrap();
+    assert_eq!(used_utxos.len(), 1);
+    assert_eq!(outpoints, parse_outpoints(&[outpoint.to_string()]).unwrap());
+    assert_eq!(change_amount, Amount::from_sat(10000));
+    assert!(!time_lock.unwrap().is_final);
+    assert_eq!(
+        bitcoin.utxo_locks.locked_outpoints(),
+        outpoints.into_iter().collect()
+    );
+}
+
 // #[tokio::test]
 // async fn test_find_spendable_utxos() {
 //     // All mainnet txn hashes
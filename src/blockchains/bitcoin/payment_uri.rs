@@ -0,0 +1,162 @@
+use bitcoin::{Amount, Denomination};
+use url::form_urlencoded;
+
+use crate::{
+    btc_api_error::BtcApiError,
+    models::{CreatePaymentUriParams, PaymentUriData},
+};
+
+const BIP21_SCHEME: &str = "bitcoin:";
+
+// Parses a BIP21 URI. The address is not checked against the network here, unknown optional
+// parameters are ignored and unknown required (req-) parameters are rejected.
+pub fn parse_payment_uri(uri: &str) -> Result<PaymentUriData, BtcApiError> {
+    let uri = uri.trim();
+
+    let scheme = uri.get(..BIP21_SCHEME.len()).unwrap_or_default();
+    if !scheme.eq_ignore_ascii_case(BIP21_SCHEME) {
+        return Err(BtcApiError::InvalidPaymentUri(format!(
+            "Expected a {} URI: {}",
+            BIP21_SCHEME, uri
+        )));
+    }
+
+    let (address, query) = match uri[BIP21_SCHEME.len()..].split_once('?') {
+        Some((address, query)) => (address, query),
+        None => (&uri[BIP21_SCHEME.len()..], ""),
+    };
+
+    if address.is_empty() {
+        return Err(BtcApiError::InvalidPaymentUri(format!(
+            "Missing address: {}",
+            uri
+        )));
+    }
+
+    let mut payment_uri = PaymentUriData {
+        uri: uri.to_string(),
+        address: address.to_string(),
+        amount: None,
+        label: None,
+        message: None,
+        lightning: None,
+        pj: None,
+    };
+
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "amount" => {
+                let amount = Amount::from_str_in(&value, Denomination::Bitcoin).map_err(|err| {
+                    BtcApiError::InvalidPaymentUri(format!("Invalid amount {}: {}", value, err))
+                })?;
+                payment_uri.amount = Some(amount);
+            }
+            "label" => payment_uri.label = Some(value.into_owned()),
+            "message" => payment_uri.message = Some(value.into_owned()),
+            "lightning" => payment_uri.lightning = Some(value.into_owned()),
+            "pj" => payment_uri.pj = Some(value.into_owned()),
+            key if key.starts_with("req-") => {
+                return Err(BtcApiError::InvalidPaymentUri(format!(
+                    "Unsupported required parameter: {}",
+                    key
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(payment_uri)
+}
+
+pub fn create_payment_uri(params: &CreatePaymentUriParams) -> Result<PaymentUriData, BtcApiError> {
+    let mut query = vec![];
+
+    if let Some(amount) = params.amount {
+        query.push(format!(
+            "amount={}",
+            amount.display_in(Denomination::Bitcoin)
+        ));
+    }
+
+    let text_params = [
+        ("label", &params.label),
+        ("message", &params.message),
+        ("lightning", &params.lightning),
+        ("pj", &params.pj),
+    ];
+    for (key, value) in text_params {
+        if let Some(value) = value {
+            // BIP21 uses percent encoding, spaces are not encoded as '+'
+            let value = form_urlencoded::byte_serialize(value.as_bytes())
+                .collect::<String>()
+                .replace('+', "%20");
+            query.push(format!("{}={}", key, value));
+        }
+    }
+
+    let mut uri = format!("{}{}", BIP21_SCHEME, params.address);
+    if !query.is_empty() {
+        uri = format!("{}?{}", uri, query.join("&"));
+    }
+
+    Ok(PaymentUriData {
+        uri,
+        address: params.address.clone(),
+        amount: params.amount,
+        label: params.label.clone(),
+        message: params.message.clone(),
+        lightning: params.lightning.clone(),
+        pj: params.pj.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "tb1q4a0umk5zxq302kfzqg5qfxta5jcp46ws6r0pjy";
+
+    #[test]
+    fn test_parse_payment_uri() {
+        let payment_uri = parse_payment_uri(&format!(
+            "BITCOIN:{}?amount=0.0012&label=Order%20%2342&message=Thanks&pj=https://example.com/pj&other=1",
+            ADDRESS
+        ))
+        .unwrap();
+
+        assert_eq!(payment_uri.address, ADDRESS);
+        assert_eq!(payment_uri.amount, Some(Amount::from_sat(120_000)));
+        assert_eq!(payment_uri.label.as_deref(), Some("Order #42"));
+        assert_eq!(payment_uri.message.as_deref(), Some("Thanks"));
+        assert_eq!(payment_uri.pj.as_deref(), Some("https://example.com/pj"));
+        assert_eq!(payment_uri.lightning, None);
+
+        assert!(parse_payment_uri(&format!("bitcoin:{}?req-somethingnew=1", ADDRESS)).is_err());
+        assert!(parse_payment_uri(&format!("bitcoin:{}?amount=abc", ADDRESS)).is_err());
+        assert!(parse_payment_uri(&format!("litecoin:{}", ADDRESS)).is_err());
+        assert!(parse_payment_uri("bitcoin:?amount=1").is_err());
+    }
+
+    #[test]
+    fn test_create_payment_uri() {
+        let params = CreatePaymentUriParams {
+            address: ADDRESS.to_string(),
+            amount: Some(Amount::from_sat(120_000)),
+            label: Some("Order #42".to_string()),
+            message: None,
+            lightning: None,
+            pj: None,
+        };
+
+        let payment_uri = create_payment_uri(&params).unwrap();
+        assert_eq!(
+            payment_uri.uri,
+            format!("bitcoin:{}?amount=0.0012&label=Order%20%2342", ADDRESS)
+        );
+
+        // Round trip
+        let parsed = parse_payment_uri(&payment_uri.uri).unwrap();
+        assert_eq!(parsed.amount, params.amount);
+        assert_eq!(parsed.label, params.label);
+    }
+}
@@ -0,0 +1,202 @@
+use std::{collections::HashMap, fs};
+
+use reqwest::{Client, Url};
+use serde::Deserialize;
+
+use crate::{btc_api_error::BtcApiError, models::FiatPrice};
+
+// Price of 1 BTC in a fiat currency
+#[async_trait::async_trait]
+#[mockall::automock]
+pub trait PriceFeed: std::fmt::Debug + Send + Sync {
+    // Latest price at or before timestamp, the current price when timestamp is None
+    async fn get_price(
+        &self,
+        currency: &str,
+        timestamp: Option<u64>,
+    ) -> Result<FiatPrice, BtcApiError>;
+}
+
+// Prices loaded from a JSON file of currency to [timestamp, price] pairs, e.g.
+// { "USD": [[1738195200, 104500.0], [1738281600, 102400.0]] }
+#[derive(Debug)]
+pub struct FilePriceFeed {
+    prices: HashMap<String, Vec<(u64, f64)>>,
+}
+
+impl FilePriceFeed {
+    pub fn load(path: &str) -> Result<Self, BtcApiError> {
+        let prices = fs::read_to_string(path)
+            .map_err(|err| BtcApiError::ConfigLoadError(format!("{}: {}", path, err)))?;
+
+        Ok(Self::new(serde_json::from_str(&prices)?))
+    }
+
+    pub fn new(prices: HashMap<String, Vec<(u64, f64)>>) -> Self {
+        let prices = prices
+            .into_iter()
+            .map(|(currency, mut prices)| {
+                prices.sort_by_key(|(timestamp, _)| *timestamp);
+                (currency.to_uppercase(), prices)
+            })
+            .collect();
+
+        Self { prices }
+    }
+}
+
+#[async_trait::async_trait]
+impl PriceFeed for FilePriceFeed {
+    async fn get_price(
+        &self,
+        currency: &str,
+        timestamp: Option<u64>,
+    ) -> Result<FiatPrice, BtcApiError> {
+        let currency = currency.to_uppercase();
+        let prices = self.prices.get(&currency).ok_or_else(|| {
+            BtcApiError::PriceFeedError(format!("No prices for currency: {}", currency))
+        })?;
+
+        let price = match timestamp {
+            Some(timestamp) => prices.iter().rev().find(|(time, _)| *time <= timestamp),
+            None => prices.last(),
+        };
+
+        let (time, price) = price.ok_or_else(|| {
+            BtcApiError::PriceFeedError(format!(
+                "No {} price at or before: {}",
+                currency,
+                timestamp.unwrap_or_default()
+            ))
+        })?;
+
+        Ok(FiatPrice {
+            currency,
+            price: *price,
+            time: *time,
+        })
+    }
+}
+
+// mempool.space compatible REST API, /v1/prices and /v1/historical-price
+#[derive(Debug)]
+pub struct HttpPriceFeed {
+    url: Url,
+    client: Client,
+}
+
+// Prices keyed by currency, along with a "time" key
+#[derive(Deserialize, Debug)]
+struct MempoolSpaceHistoricalPrices {
+    prices: Vec<HashMap<String, f64>>,
+}
+
+impl HttpPriceFeed {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PriceFeed for HttpPriceFeed {
+    async fn get_price(
+        &self,
+        currency: &str,
+        timestamp: Option<u64>,
+    ) -> Result<FiatPrice, BtcApiError> {
+        let currency = currency.to_uppercase();
+
+        let prices = match timestamp {
+            Some(timestamp) => {
+                let mut url = self.url.join("v1/historical-price")?;
+                url.query_pairs_mut()
+                    .append_pair("currency", &currency)
+                    .append_pair("timestamp", &timestamp.to_string());
+
+                self.client
+                    .get(url)
+                    .send()
+                    .await?
+                    .json::<MempoolSpaceHistoricalPrices>()
+                    .await?
+                    .prices
+                    .into_iter()
+                    .next()
+                    .unwrap_or_default()
+            }
+            None => {
+                self.client
+                    .get(self.url.join("v1/prices")?)
+                    .send()
+                    .await?
+                    .json::<HashMap<String, f64>>()
+                    .await?
+            }
+        };
+
+        match (prices.get(&currency), prices.get("time")) {
+            // mempool.space reports 0 for the periods it has no price of
+            (Some(price), Some(time)) if *price > 0.0 => Ok(FiatPrice {
+                currency,
+                price: *price,
+                time: *time as u64,
+            }),
+            _ => Err(BtcApiError::PriceFeedError(format!(
+                "No {} price from: {}",
+                currency, self.url
+            ))),
+        }
+    }
+}
+
+// Value of an amount in BTC, rounded to cents
+pub fn to_fiat(btc: f64, price: &FiatPrice) -> f64 {
+    (btc * price.price * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{Amount, SignedAmount};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_price_feed() {
+        let price_feed = FilePriceFeed::new(HashMap::from([(
+            "usd".to_string(),
+            vec![(1738281600, 102400.0), (1738195200, 104500.0)],
+        )]));
+
+        // Latest price when no timestamp is given
+        let price = price_feed.get_price("USD", None).await.unwrap();
+        assert_eq!(price.price, 102400.0);
+        assert_eq!(price.time, 1738281600);
+
+        // Latest price at or before the timestamp
+        let price = price_feed.get_price("usd", Some(1738199336)).await.unwrap();
+        assert_eq!(price.price, 104500.0);
+        assert_eq!(price.currency, "USD");
+
+        assert!(price_feed.get_price("USD", Some(1738000000)).await.is_err());
+        assert!(price_feed.get_price("EUR", None).await.is_err());
+    }
+
+    #[test]
+    fn test_to_fiat() {
+        let price = FiatPrice {
+            currency: "USD".to_string(),
+            price: 104500.0,
+            time: 1738195200,
+        };
+
+        assert_eq!(to_fiat(Amount::from_sat(30000).to_btc(), &price), 31.35);
+        assert_eq!(
+            to_fiat(SignedAmount::from_sat(-12345).to_btc(), &price),
+            -12.9
+        );
+        assert_eq!(to_fiat(0.0, &price), 0.0);
+    }
+}
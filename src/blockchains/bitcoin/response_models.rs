@@ -1,8 +1,11 @@
 // Type of response from blockchain.info/rawtx/<transaction_hash>
 
+use std::{collections::HashMap, str::FromStr};
+
+use bitcoin::{Amount, OutPoint, SignedAmount, Txid};
 use serde::{Deserialize, Serialize};
 
-use crate::models::AddressSpent;
+use crate::{btc_api_error::BtcApiError, models::AddressSpent};
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(untagged)]
@@ -31,7 +34,8 @@ pub struct BlockchaincomRawTxn {
     lock_time: u64,
     tx_index: u64,
     pub double_spend: bool,
-    time: u64,
+    // Unix time at which blockchain.info received the transaction
+    pub time: u64,
     pub block_index: Option<u64>,
     pub block_height: Option<u64>,
     pub inputs: Vec<Input>,
@@ -73,7 +77,7 @@ impl BlockchaincomRawTxn {
             .iter()
             .map(|input| AddressSpent {
                 address: input.prev_out.addr.clone().unwrap_or("Unknown".to_string()),
-                amount: input.prev_out.value,
+                amount: Amount::from_sat(input.prev_out.value),
             })
             .collect::<Vec<AddressSpent>>()
     }
@@ -83,55 +87,213 @@ impl BlockchaincomRawTxn {
             .iter()
             .map(|out| AddressSpent {
                 address: out.addr.clone().unwrap_or("Unknown".to_string()),
-                amount: out.value,
+                amount: Amount::from_sat(out.value),
             })
             .collect::<Vec<AddressSpent>>()
     }
 
-    pub fn get_total_input_amount(&self) -> u64 {
+    pub fn get_total_input_amount(&self) -> Amount {
         self.inputs
             .iter()
-            .map(|input| input.prev_out.value)
-            .sum::<u64>()
+            .map(|input| Amount::from_sat(input.prev_out.value))
+            .sum::<Amount>()
     }
 
-    pub fn get_total_output_amount(&self) -> u64 {
-        self.out.iter().map(|out| out.value).sum::<u64>()
+    pub fn get_total_output_amount(&self) -> Amount {
+        self.out
+            .iter()
+            .map(|out| Amount::from_sat(out.value))
+            .sum::<Amount>()
     }
 
-    pub fn get_total_fee(&self) -> u64 {
-        self.fee
+    pub fn get_total_fee(&self) -> Amount {
+        Amount::from_sat(self.fee)
     }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Ord, PartialOrd, PartialEq, Eq)]
 pub struct BlockstreamUtxo {
-    txid: String,
-    vout: u32,
-    status: Status,
+    pub txid: String,
+    pub vout: u32,
+    pub status: Status,
     pub value: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Ord, Eq, PartialEq, PartialOrd)]
 pub struct Status {
-    confirmed: bool,
-    block_height: Option<u64>,
-    block_hash: Option<String>,
-    block_time: Option<u64>,
+    pub confirmed: bool,
+    pub block_height: Option<u64>,
+    pub block_hash: Option<String>,
+    pub block_time: Option<u64>,
 }
 
-impl BlockstreamUtxo {
-    pub fn get_txid(&self) -> String {
-        self.txid.clone()
+impl Status {
+    pub fn get_confirmations(&self, tip_height: u64) -> u64 {
+        match self.block_height {
+            Some(block_height) if self.confirmed => (tip_height + 1).saturating_sub(block_height),
+            _ => 0,
+        }
     }
+}
 
-    pub fn get_vout(&self) -> u32 {
-        self.vout
+impl BlockstreamUtxo {
+    pub fn get_outpoint(&self) -> Result<OutPoint, BtcApiError> {
+        Ok(OutPoint::new(Txid::from_str(&self.txid)?, self.vout))
     }
 
     pub fn is_confirmed(&self) -> bool {
         self.status.confirmed
     }
+
+    pub fn get_confirmations(&self, tip_height: u64) -> u64 {
+        self.status.get_confirmations(tip_height)
+    }
+}
+
+// Type of response from blockstream /tx/<txid> and /address/<address>/txs
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockstreamTxn {
+    pub txid: String,
+    pub version: u32,
+    pub locktime: u32,
+    pub vin: Vec<BlockstreamVin>,
+    pub vout: Vec<BlockstreamVout>,
+    pub size: u64,
+    pub weight: u64,
+    //Transaction fee in satoshis
+    pub fee: u64,
+    pub status: Status,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockstreamVin {
+    pub txid: String,
+    pub vout: u32,
+    // None for coinbase inputs
+    pub prevout: Option<BlockstreamVout>,
+    pub scriptsig: String,
+    pub witness: Option<Vec<String>>,
+    pub is_coinbase: bool,
+    pub sequence: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockstreamVout {
+    pub scriptpubkey: String,
+    pub scriptpubkey_type: String,
+    pub scriptpubkey_address: Option<String>,
+    pub value: u64,
+}
+
+impl BlockstreamTxn {
+    pub fn get_input_txns(&self) -> Vec<AddressSpent> {
+        self.vin
+            .iter()
+            .filter_map(|vin| vin.prevout.as_ref())
+            .map(BlockstreamVout::to_address_spent)
+            .collect::<Vec<AddressSpent>>()
+    }
+
+    pub fn get_output_txns(&self) -> Vec<AddressSpent> {
+        self.vout
+            .iter()
+            .map(BlockstreamVout::to_address_spent)
+            .collect::<Vec<AddressSpent>>()
+    }
+
+    // Amount received by the address minus the amount it spent in this transaction
+    pub fn get_net_amount(&self, address: &str) -> SignedAmount {
+        let received = self
+            .vout
+            .iter()
+            .filter(|vout| vout.scriptpubkey_address.as_deref() == Some(address))
+            .map(|vout| vout.value as i64)
+            .sum::<i64>();
+
+        let spent = self
+            .vin
+            .iter()
+            .filter_map(|vin| vin.prevout.as_ref())
+            .filter(|prevout| prevout.scriptpubkey_address.as_deref() == Some(address))
+            .map(|prevout| prevout.value as i64)
+            .sum::<i64>();
+
+        SignedAmount::from_sat(received - spent)
+    }
+}
+
+impl BlockstreamVout {
+    fn to_address_spent(&self) -> AddressSpent {
+        AddressSpent {
+            address: self
+                .scriptpubkey_address
+                .clone()
+                .unwrap_or("Unknown".to_string()),
+            amount: Amount::from_sat(self.value),
+        }
+    }
+}
+
+// Type of response from blockstream /tx/<txid>/outspend/<vout>
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BlockstreamOutspend {
+    pub spent: bool,
+    // Transaction spending the output
+    pub txid: Option<String>,
+}
+
+// Type of response from blockstream /block/<hash>
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockstreamBlock {
+    pub id: String,
+    pub height: u64,
+    pub timestamp: u64,
+    // Median time past, what timestamp lock times are compared with
+    pub mediantime: u64,
+    pub tx_count: u64,
+    pub size: u64,
+    pub weight: u64,
+    // None for the genesis block
+    pub previousblockhash: Option<String>,
+}
+
+// Type of response from bitcoind submitpackage
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BitcoindSubmitPackageResult {
+    pub package_msg: String,
+    // Keyed by wtxid
+    #[serde(rename = "tx-results")]
+    pub tx_results: HashMap<String, BitcoindPackageTxResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BitcoindPackageTxResult {
+    pub txid: String,
+    // Reject reason of the transaction, absent when it was accepted
+    pub error: Option<String>,
+}
+
+// Type of response from blockstream /mempool
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BlockstreamMempool {
+    pub count: u64,
+    pub vsize: u64,
+    // In satoshis
+    pub total_fee: u64,
+    // (fee rate in sat/vB, vsize) pairs from the highest fee rate down, each bucket holds the
+    // transactions paying at least its fee rate and less than the previous one
+    pub fee_histogram: Vec<(f64, u64)>,
+}
+
+// Type of response from mempool.space /v1/fees/recommended, in sat/vB
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MempoolSpaceFees {
+    pub fastest_fee: f64,
+    pub half_hour_fee: f64,
+    pub hour_fee: f64,
+    pub economy_fee: f64,
+    pub minimum_fee: f64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -150,13 +312,20 @@ pub struct Stats {
     tx_count: u64,
 }
 
+impl Stats {
+    // Negative for the mempool when it spends more confirmed outputs than it funds
+    fn get_balance(&self) -> SignedAmount {
+        SignedAmount::from_sat(self.funded_txo_sum) - SignedAmount::from_sat(self.spent_txo_sum)
+    }
+}
+
 impl BlockstreamWalletBalance {
-    pub fn get_confirmed_balance(&self) -> i64 {
-        self.chain_stats.funded_txo_sum - self.chain_stats.spent_txo_sum
+    pub fn get_confirmed_balance(&self) -> SignedAmount {
+        self.chain_stats.get_balance()
     }
 
-    pub fn get_unconfirmed_balance(&self) -> i64 {
-        self.mempool_stats.funded_txo_sum - self.mempool_stats.spent_txo_sum
+    pub fn get_unconfirmed_balance(&self) -> SignedAmount {
+        self.mempool_stats.get_balance()
     }
 }
 
@@ -322,6 +491,161 @@ mod tests {
 
         let deserialized_json = serde_json::from_str::<Vec<BlockstreamUtxo>>(json);
         assert!(deserialized_json.is_ok()); // Successfully deserialized response from blockstream ✅
-        assert!(deserialized_json.unwrap().first().unwrap().is_confirmed());
+
+        let utxos = deserialized_json.unwrap();
+        assert!(utxos.first().unwrap().is_confirmed());
+        assert_eq!(utxos[0].get_confirmations(3659270), 4);
+        assert_eq!(utxos[1].get_confirmations(3659270), 0);
+    }
+
+    #[test]
+    fn test_de_blockstream_txn_response() {
+        let json = r#"
+        {
+            "txid": "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2",
+            "version": 2,
+            "locktime": 3659266,
+            "vin": [
+                {
+                    "txid": "a2a9afba41ea32a4c04e8984e84593796de447ac7b8f6caed9265ef332b21223",
+                    "vout": 1,
+                    "prevout": {
+                        "scriptpubkey": "0014af5fcdda823022f56922022804997da4b01ae9d0",
+                        "scriptpubkey_asm": "OP_0 OP_PUSHBYTES_20 af5fcdda823022f56922022804997da4b01ae9d0",
+                        "scriptpubkey_type": "v0_p2wpkh",
+                        "scriptpubkey_address": "tb1q4a0umk5zxq302kfzqg5qfxta5jcp46ws6r0pjy",
+                        "value": 50000
+                    },
+                    "scriptsig": "",
+                    "scriptsig_asm": "",
+                    "witness": [
+                        "3044022015b6a6b2a4f8f3b5f1fd1e4d7b4b0c0e5f8bd1c16a8b6d0b17a36e4b3b4e6b7c02207a2b6a8c6d9f5c2bd5e1c1e9a2c4c1e1d9e6a8f2d7f0a4b5c9d3e2f1a0b9c8d701",
+                        "0368db5fff504f996f887c23200967e17eccdca3dd1956427f39513c410ea4c86f"
+                    ],
+                    "is_coinbase": false,
+                    "sequence": 4294967293
+                }
+            ],
+            "vout": [
+                {
+                    "scriptpubkey": "0014c6ab9f5c1f5d3d6e8ad3c7c3c1a0b6f0e2d8d3e1",
+                    "scriptpubkey_asm": "OP_0 OP_PUSHBYTES_20 c6ab9f5c1f5d3d6e8ad3c7c3c1a0b6f0e2d8d3e1",
+                    "scriptpubkey_type": "v0_p2wpkh",
+                    "scriptpubkey_address": "tb1qc64e7hqlt57kaz5nclpurg9k7r3d35lpyxr3rl",
+                    "value": 39649
+                },
+                {
+                    "scriptpubkey": "0014af5fcdda823022f56922022804997da4b01ae9d0",
+                    "scriptpubkey_asm": "OP_0 OP_PUSHBYTES_20 af5fcdda823022f56922022804997da4b01ae9d0",
+                    "scriptpubkey_type": "v0_p2wpkh",
+                    "scriptpubkey_address": "tb1q4a0umk5zxq302kfzqg5qfxta5jcp46ws6r0pjy",
+                    "value": 10210
+                }
+            ],
+            "size": 222,
+            "weight": 561,
+            "fee": 141,
+            "status": {
+                "confirmed": true,
+                "block_height": 3659267,
+                "block_hash": "00000000000000a54221360b8c9286bfeba1951e7bf3b47e2a5680d982a12c8e",
+                "block_time": 1738199336
+            }
+        }
+        "#;
+
+        let blockstream_txn = serde_json::from_str::<BlockstreamTxn>(json).unwrap();
+
+        assert_eq!(
+            blockstream_txn.get_net_amount("tb1q4a0umk5zxq302kfzqg5qfxta5jcp46ws6r0pjy"),
+            SignedAmount::from_sat(-39790)
+        );
+        assert_eq!(
+            blockstream_txn.get_net_amount("tb1qc64e7hqlt57kaz5nclpurg9k7r3d35lpyxr3rl"),
+            SignedAmount::from_sat(39649)
+        );
+        assert_eq!(blockstream_txn.get_input_txns().len(), 1);
+        assert_eq!(blockstream_txn.get_output_txns().len(), 2);
+    }
+
+    #[test]
+    fn test_de_bitcoind_submit_package_result() {
+        let json = r#"
+        {
+            "package_msg": "transaction failed",
+            "tx-results": {
+                "a2a9afba41ea32a4c04e8984e84593796de447ac7b8f6caed9265ef332b21223": {
+                    "txid": "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2",
+                    "vsize": 141,
+                    "fees": { "base": 0.00000000 }
+                },
+                "69f8ab2bf2d82b3e5fd7626736d040d9c11d4ea3c31fb0c30bb0d72e8c5a6238": {
+                    "txid": "d6db69946d2eece44bcda9e6beb2e859ad627662b53a917679b9ea8e70e1d60f",
+                    "error": "bad-txns-inputs-missingorspent"
+                }
+            },
+            "replaced-transactions": []
+        }
+        "#;
+
+        let package_result = serde_json::from_str::<BitcoindSubmitPackageResult>(json).unwrap();
+
+        assert_eq!(package_result.package_msg, "transaction failed");
+        assert_eq!(package_result.tx_results.len(), 2);
+        assert!(package_result.tx_results
+            ["a2a9afba41ea32a4c04e8984e84593796de447ac7b8f6caed9265ef332b21223"]
+            .error
+            .is_none());
+        assert_eq!(
+            package_result.tx_results
+                ["69f8ab2bf2d82b3e5fd7626736d040d9c11d4ea3c31fb0c30bb0d72e8c5a6238"]
+                .error
+                .as_deref(),
+            Some("bad-txns-inputs-missingorspent")
+        );
+    }
+
+    #[test]
+    fn test_de_blockstream_mempool() {
+        let json = r#"
+        {
+            "count": 8134,
+            "vsize": 3444604,
+            "total_fee": 29204625,
+            "fee_histogram": [[53.01, 102131], [38.56, 110990], [2.0, 3231483]]
+        }
+        "#;
+
+        let mempool: BlockstreamMempool = serde_json::from_str(json).unwrap();
+        assert_eq!(mempool.count, 8134);
+        assert_eq!(mempool.fee_histogram.len(), 3);
+        assert_eq!(mempool.fee_histogram[0], (53.01, 102131));
+    }
+
+    #[test]
+    fn test_de_blockstream_block() {
+        let json = r#"
+        {
+            "id": "00000000000000a54221360b8c9286bfeba1951e7bf3b47e2a5680d982a12c8e",
+            "height": 3659267,
+            "version": 536870912,
+            "timestamp": 1738199336,
+            "tx_count": 183,
+            "size": 84517,
+            "weight": 203296,
+            "merkle_root": "5c1bd2d47b8a2ee1e3ca4c0ec17cfc3b6d5e1b8b3bbd6c1ffe1fd0e4a38b8f0a",
+            "previousblockhash": "000000000000001d68e3fa2ba3a55d0e1e2c51328a2fe4a9c5b25d1fce1e7d3c",
+            "mediantime": 1738196419,
+            "nonce": 3212633770,
+            "bits": 421617535,
+            "difficulty": 1
+        }
+        "#;
+
+        let block: BlockstreamBlock = serde_json::from_str(json).unwrap();
+        assert_eq!(block.height, 3659267);
+        assert_eq!(block.tx_count, 183);
+        assert_eq!(block.weight, 203296);
+        assert!(block.previousblockhash.is_some());
     }
 }
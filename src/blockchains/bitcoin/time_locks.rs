@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use bitcoin::{absolute, relative, OutPoint, Sequence};
+
+use crate::{
+    blockchains::bitcoin::{
+        coin_selection::parse_outpoints,
+        response_models::{BlockstreamBlock, BlockstreamUtxo},
+    },
+    btc_api_error::BtcApiError,
+    models::{RelativeLockTime, TimeLockInfo},
+};
+
+// Absolute (nLockTime) and relative (BIP68) lock times requested for a transaction
+#[derive(Debug, Default)]
+pub struct TimeLocks {
+    pub lock_time: Option<absolute::LockTime>,
+    pub relative: HashMap<OutPoint, relative::LockTime>,
+    // Median time past of the block before the one which confirmed the UTXO, where a time
+    // based relative lock starts. Missing for unconfirmed UTXOs.
+    pub relative_lock_starts: HashMap<OutPoint, u64>,
+}
+
+impl TimeLocks {
+    // Relative lock times can only be set on the included UTXOs, the caller has to know which
+    // input they apply to
+    pub fn parse(
+        lock_time: Option<u32>,
+        relative_lock_times: &HashMap<String, RelativeLockTime>,
+        include_utxos: &[String],
+    ) -> Result<Self, BtcApiError> {
+        let included = parse_outpoints(include_utxos)?;
+
+        let mut relative = HashMap::new();
+        for (outpoint, relative_lock_time) in relative_lock_times {
+            let outpoint = parse_outpoints(std::slice::from_ref(outpoint))?[0];
+            if !included.contains(&outpoint) {
+                return Err(BtcApiError::InvalidLockTime(format!(
+                    "Relative lock time set on {} which is not in include_utxos",
+                    outpoint
+                )));
+            }
+
+            let relative_lock_time = match relative_lock_time {
+                RelativeLockTime::Blocks(blocks) => relative::LockTime::from_height(*blocks),
+                RelativeLockTime::Seconds(seconds) => {
+                    relative::LockTime::from_seconds_ceil(*seconds).map_err(|err| {
+                        BtcApiError::InvalidLockTime(format!("{}: {}", outpoint, err))
+                    })?
+                }
+            };
+
+            relative.insert(outpoint, relative_lock_time);
+        }
+
+        Ok(Self {
+            lock_time: lock_time.map(absolute::LockTime::from_consensus),
+            relative,
+            relative_lock_starts: HashMap::new(),
+        })
+    }
+
+    // Outpoints whose relative lock is time based, their start has to be fetched
+    pub fn time_based_outpoints(&self) -> Vec<OutPoint> {
+        self.relative
+            .iter()
+            .filter(|(_, relative_lock_time)| {
+                matches!(relative_lock_time, relative::LockTime::Time(_))
+            })
+            .map(|(outpoint, _)| *outpoint)
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock_time.is_none() && self.relative.is_empty()
+    }
+
+    pub fn get_lock_time(&self) -> absolute::LockTime {
+        self.lock_time.unwrap_or(absolute::LockTime::ZERO)
+    }
+
+    // nLockTime is only enforced when at least one input does not use the max sequence
+    pub fn get_sequence(&self, outpoint: &OutPoint) -> Sequence {
+        match self.relative.get(outpoint) {
+            Some(relative_lock_time) => relative_lock_time.to_sequence(),
+            None if self.lock_time.is_some() => Sequence::ENABLE_LOCKTIME_NO_RBF,
+            None => Sequence::MAX,
+        }
+    }
+
+    // Earliest block height and median time past at which the transaction can be mined, given
+    // the spent UTXOs. As in BIP68, time based relative locks are counted from the median time
+    // past of the block before the one which confirmed the UTXO, and every lock is compared
+    // with the median time past of the tip.
+    pub fn get_time_lock_info(
+        &self,
+        used_utxos: &[BlockstreamUtxo],
+        tip_height: u64,
+        tip_median_time: u64,
+    ) -> Result<TimeLockInfo, BtcApiError> {
+        let mut final_at_height = None;
+        let mut final_at_time = None;
+        // Relative locks of unconfirmed UTXOs only start counting once they are mined
+        let mut has_unconfirmed_relative_lock = false;
+
+        match self.lock_time {
+            Some(absolute::LockTime::Blocks(height)) => {
+                final_at_height = Some(height.to_consensus_u32() as u64 + 1);
+            }
+            Some(absolute::LockTime::Seconds(time)) => {
+                final_at_time = Some(time.to_consensus_u32() as u64 + 1);
+            }
+            None => {}
+        }
+
+        for utxo in used_utxos {
+            let outpoint = utxo.get_outpoint()?;
+            let Some(relative_lock_time) = self.relative.get(&outpoint) else {
+                continue;
+            };
+
+            match (
+                relative_lock_time,
+                utxo.status.block_height,
+                self.relative_lock_starts.get(&outpoint),
+            ) {
+                (relative::LockTime::Blocks(height), Some(block_height), _) => {
+                    final_at_height =
+                        final_at_height.max(Some(block_height + height.value() as u64));
+                }
+                (relative::LockTime::Time(time), _, Some(lock_start)) => {
+                    final_at_time = final_at_time.max(Some(lock_start + time.value() as u64 * 512));
+                }
+                _ => has_unconfirmed_relative_lock = true,
+            }
+        }
+
+        let is_final = !has_unconfirmed_relative_lock
+            && final_at_height.is_none_or(|height| height <= tip_height + 1)
+            && final_at_time.is_none_or(|time| time <= tip_median_time);
+
+        Ok(TimeLockInfo {
+            lock_time: self.get_lock_time().to_consensus_u32(),
+            tip_height,
+            tip_median_time,
+            final_at_height,
+            final_at_time,
+            is_final,
+        })
+    }
+
+    // Time lock info against the tip, which is only given when lock times are requested. The
+    // mempool rejects a transaction which is not final yet, so it is an error unless
+    // allow_non_final is set.
+    pub fn check(
+        &self,
+        used_utxos: &[BlockstreamUtxo],
+        tip_block: Option<&BlockstreamBlock>,
+        allow_non_final: bool,
+    ) -> Result<Option<TimeLockInfo>, BtcApiError> {
+        let Some(tip_block) = tip_block else {
+            return Ok(None);
+        };
+
+        let time_lock =
+            self.get_time_lock_info(used_utxos, tip_block.height, tip_block.mediantime)?;
+        if !time_lock.is_final && !allow_non_final {
+            return Err(BtcApiError::InvalidLockTime(format!(
+                "Transaction is not final at tip height {} (final at height {:?}, median time {:?}), set allow_non_final to build it anyway",
+                time_lock.tip_height, time_lock.final_at_height, time_lock.final_at_time
+            )));
+        }
+
+        Ok(Some(time_lock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INCLUDED_UTXO: &str =
+        "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2:0";
+
+    fn utxo() -> BlockstreamUtxo {
+        serde_json::from_str(
+            r#"{
+                "txid": "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2",
+                "vout": 0,
+                "status": { "confirmed": true, "block_height": 3659267, "block_time": 1738199336 },
+                "value": 30000
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_time_locks() {
+        let outpoint = INCLUDED_UTXO.parse::<OutPoint>().unwrap();
+        let relative_lock_times =
+            HashMap::from([(INCLUDED_UTXO.to_string(), RelativeLockTime::Blocks(144))]);
+
+        let time_locks = TimeLocks::parse(
+            Some(3659300),
+            &relative_lock_times,
+            &[INCLUDED_UTXO.to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            time_locks.get_sequence(&outpoint),
+            Sequence::from_height(144)
+        );
+        assert_eq!(
+            time_locks.get_sequence(&OutPoint::null()),
+            Sequence::ENABLE_LOCKTIME_NO_RBF
+        );
+
+        // Relative lock times only apply to the included UTXOs
+        assert!(matches!(
+            TimeLocks::parse(None, &relative_lock_times, &[]),
+            Err(BtcApiError::InvalidLockTime(_))
+        ));
+
+        // No lock keeps the inputs final
+        let time_locks = TimeLocks::parse(None, &HashMap::new(), &[]).unwrap();
+        assert!(time_locks.is_empty());
+        assert_eq!(time_locks.get_sequence(&outpoint), Sequence::MAX);
+    }
+
+    #[test]
+    fn test_get_time_lock_info() {
+        let relative_lock_times =
+            HashMap::from([(INCLUDED_UTXO.to_string(), RelativeLockTime::Blocks(144))]);
+        let time_locks = TimeLocks::parse(
+            Some(3659300),
+            &relative_lock_times,
+            &[INCLUDED_UTXO.to_string()],
+        )
+        .unwrap();
+
+        // The relative lock ends later than the absolute one
+        let info = time_locks
+            .get_time_lock_info(&[utxo()], 3659300, 1738200000)
+            .unwrap();
+        assert_eq!(info.final_at_height, Some(3659267 + 144));
+        assert!(!info.is_final);
+
+        let info = time_locks
+            .get_time_lock_info(&[utxo()], 3659267 + 143, 1738200000)
+            .unwrap();
+        assert!(info.is_final);
+
+        // Time based relative locks start at the median time past before the UTXO was mined,
+        // which is earlier than its block time
+        let relative_lock_times =
+            HashMap::from([(INCLUDED_UTXO.to_string(), RelativeLockTime::Seconds(5120))]);
+        let mut time_locks =
+            TimeLocks::parse(None, &relative_lock_times, &[INCLUDED_UTXO.to_string()]).unwrap();
+        assert_eq!(
+            time_locks.time_based_outpoints(),
+            vec![INCLUDED_UTXO.parse::<OutPoint>().unwrap()]
+        );
+
+        // Not final while the start is unknown
+        let info = time_locks
+            .get_time_lock_info(&[utxo()], 3659300, 1738210000)
+            .unwrap();
+        assert!(!info.is_final);
+
+        time_locks.relative_lock_starts =
+            HashMap::from([(INCLUDED_UTXO.parse::<OutPoint>().unwrap(), 1738196000)]);
+        let info = time_locks
+            .get_time_lock_info(&[utxo()], 3659300, 1738201119)
+            .unwrap();
+        assert_eq!(info.final_at_time, Some(1738196000 + 5120));
+        assert!(!info.is_final);
+
+        let info = time_locks
+            .get_time_lock_info(&[utxo()], 3659300, 1738201120)
+            .unwrap();
+        assert!(info.is_final);
+
+        // Timestamp lock times are compared with the median time past of the tip
+        let time_locks = TimeLocks::parse(Some(1738300000), &HashMap::new(), &[]).unwrap();
+        let info = time_locks
+            .get_time_lock_info(&[utxo()], 3659300, 1738200000)
+            .unwrap();
+        assert_eq!(info.final_at_time, Some(1738300001));
+        assert!(!info.is_final);
+    }
+}
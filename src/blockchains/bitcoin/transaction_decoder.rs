@@ -0,0 +1,160 @@
+use bitcoin::{Address, Amount, Network, Transaction, TxOut};
+
+use crate::{
+    btc_api_error::BtcApiError,
+    models::{DecodeTransactionResponseData, DecodedInput, DecodedOutput},
+};
+
+// Describes a transaction, the prevouts have to be in the same order as the inputs. The fee is
+// only computed when the prevouts are given.
+pub fn decode_transaction(
+    txn: &Transaction,
+    network: Network,
+    prevouts: Option<&[TxOut]>,
+) -> Result<DecodeTransactionResponseData, BtcApiError> {
+    let vsize = txn.vsize() as u64;
+    let fee = match prevouts {
+        Some(prevouts) => get_fee(txn, prevouts)?,
+        None => None,
+    };
+
+    let inputs = txn
+        .input
+        .iter()
+        .enumerate()
+        .map(|(index, input)| DecodedInput {
+            txid: input.previous_output.txid.to_string(),
+            vout: input.previous_output.vout,
+            sequence: input.sequence.to_consensus_u32(),
+            script_sig: input.script_sig.to_hex_string(),
+            witness: input.witness.iter().map(hex::encode).collect(),
+            prevout: prevouts
+                .and_then(|prevouts| prevouts.get(index))
+                .map(|prevout| decode_output(prevout, network)),
+        })
+        .collect();
+
+    Ok(DecodeTransactionResponseData {
+        txid: txn.compute_txid().to_string(),
+        wtxid: txn.compute_wtxid().to_string(),
+        version: txn.version.0,
+        lock_time: txn.lock_time.to_consensus_u32(),
+        size: txn.total_size() as u64,
+        weight: txn.weight().to_wu(),
+        vsize,
+        is_rbf: txn.is_explicitly_rbf(),
+        inputs,
+        outputs: txn
+            .output
+            .iter()
+            .map(|output| decode_output(output, network))
+            .collect(),
+        fee: fee.map(Amount::to_sat),
+        fee_rate: fee.map(|fee| fee.to_sat() as f64 / vsize as f64),
+    })
+}
+
+pub fn decode_output(output: &TxOut, network: Network) -> DecodedOutput {
+    DecodedOutput {
+        value: output.value.to_sat(),
+        script_pubkey: output.script_pubkey.to_hex_string(),
+        address: Address::from_script(&output.script_pubkey, network)
+            .ok()
+            .map(|address| address.to_string()),
+    }
+}
+
+// Sum of the spent prevouts minus the sum of the outputs, None if the outputs are worth more
+pub fn get_fee(txn: &Transaction, prevouts: &[TxOut]) -> Result<Option<Amount>, BtcApiError> {
+    let input_amount = sum_values(prevouts)?;
+    let output_amount = sum_values(&txn.output)?;
+
+    Ok(input_amount.checked_sub(output_amount))
+}
+
+// Values come from untrusted transactions, their sum can overflow
+pub fn sum_values(outputs: &[TxOut]) -> Result<Amount, BtcApiError> {
+    outputs
+        .iter()
+        .map(|output| output.value)
+        .try_fold(Amount::ZERO, Amount::checked_add)
+        .ok_or_else(|| {
+            BtcApiError::InvalidRawTransaction("Sum of the output values overflows".to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::{
+        absolute::LockTime, consensus::encode::serialize_hex, hashes::Hash, transaction::Version,
+        OutPoint, ScriptBuf, Sequence, TxIn, WPubkeyHash, Witness,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_decode_transaction() {
+        let address = Address::from_script(
+            &ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array([7; 20])),
+            Network::Testnet,
+        )
+        .unwrap();
+
+        let txn = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::from_height(3659266).unwrap(),
+            input: vec![TxIn {
+                previous_output: OutPoint::from_str(
+                    "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2:1",
+                )
+                .unwrap(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(&[vec![0x30; 71], vec![0x02; 33]]),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(9_000),
+                script_pubkey: address.script_pubkey(),
+            }],
+        };
+
+        let raw_txn = serialize_hex(&txn);
+        let decoded_txn = bitcoin::consensus::encode::deserialize_hex::<Transaction>(&raw_txn);
+        assert!(decoded_txn.is_ok());
+
+        let prevouts = vec![TxOut {
+            value: Amount::from_sat(10_000),
+            script_pubkey: address.script_pubkey(),
+        }];
+
+        let decoded =
+            decode_transaction(&decoded_txn.unwrap(), Network::Testnet, Some(&prevouts)).unwrap();
+
+        assert!(decoded.is_rbf);
+        assert_eq!(decoded.lock_time, 3659266);
+        assert_eq!(decoded.fee, Some(1_000));
+        assert_eq!(decoded.fee_rate, Some(1_000.0 / decoded.vsize as f64));
+        assert_eq!(decoded.inputs[0].witness.len(), 2);
+        assert_eq!(decoded.outputs[0].address, Some(address.to_string()));
+
+        // Fee is unknown without the prevouts
+        let decoded = decode_transaction(&txn, Network::Testnet, None).unwrap();
+        assert_eq!(decoded.fee, None);
+        assert!(decoded.inputs[0].prevout.is_none());
+
+        // Prevouts worth more than the supply in total are rejected rather than overflowing
+        let prevouts = vec![
+            TxOut {
+                value: Amount::MAX,
+                script_pubkey: address.script_pubkey(),
+            };
+            2
+        ];
+        assert!(matches!(
+            decode_transaction(&txn, Network::Testnet, Some(&prevouts)),
+            Err(BtcApiError::InvalidRawTransaction(_))
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bitcoin::OutPoint;
+use tracing::debug;
+
+// Reservation layer for the UTXOs selected by createTransaction/consolidate.
+// A selected outpoint stays locked until it is released (e.g. once the transaction spending it
+// is broadcasted) or the ttl expires, so concurrent calls never pick the same UTXOs.
+#[derive(Debug, Clone)]
+pub struct UtxoLocks {
+    locks: Arc<Mutex<HashMap<OutPoint, Instant>>>,
+    ttl: Duration,
+}
+
+impl UtxoLocks {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            locks: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    // Returns the outpoints which are currently locked
+    pub fn locked_outpoints(&self) -> HashSet<OutPoint> {
+        let mut locks = self.locks.lock().expect("utxo locks poisoned");
+        Self::remove_expired(&mut locks);
+
+        locks.keys().cloned().collect()
+    }
+
+    // Runs the selection against the currently locked outpoints and locks the outpoints it
+    // selected. Selection and locking happen under the same lock so they are atomic.
+    pub fn lock_selection<T, E>(
+        &self,
+        select: impl FnOnce(&HashSet<OutPoint>) -> Result<(T, Vec<OutPoint>), E>,
+    ) -> Result<T, E> {
+        let mut locks = self.locks.lock().expect("utxo locks poisoned");
+        Self::remove_expired(&mut locks);
+
+        let locked = locks.keys().cloned().collect::<HashSet<OutPoint>>();
+        let (selection, selected_outpoints) = select(&locked)?;
+
+        let expires_at = Instant::now() + self.ttl;
+        for outpoint in selected_outpoints {
+            debug!("Locking UTXO: {}", outpoint);
+            locks.insert(outpoint, expires_at);
+        }
+
+        Ok(selection)
+    }
+
+    // Releases the given outpoints, returns the ones which were locked
+    pub fn release(&self, outpoints: &[OutPoint]) -> Vec<OutPoint> {
+        let mut locks = self.locks.lock().expect("utxo locks poisoned");
+        Self::remove_expired(&mut locks);
+
+        outpoints
+            .iter()
+            .filter(|outpoint| locks.remove(outpoint).is_some())
+            .inspect(|outpoint| debug!("Released UTXO: {}", outpoint))
+            .cloned()
+            .collect()
+    }
+
+    fn remove_expired(locks: &mut HashMap<OutPoint, Instant>) {
+        let now = Instant::now();
+        locks.retain(|_, expires_at| *expires_at > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_lock_selection_and_release() {
+        let outpoint_a = OutPoint::from_str(
+            "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2:0",
+        )
+        .unwrap();
+        let outpoint_b = OutPoint::from_str(
+            "d6db69946d2eece44bcda9e6beb2e859ad627662b53a917679b9ea8e70e1d60f:1",
+        )
+        .unwrap();
+
+        let utxo_locks = UtxoLocks::new(Duration::from_secs(60));
+
+        // First selection locks outpoint a
+        let selected = utxo_locks.lock_selection(|locked| {
+            assert!(locked.is_empty());
+            Ok::<_, ()>((outpoint_a, vec![outpoint_a]))
+        });
+        assert_eq!(selected, Ok(outpoint_a));
+
+        // Second selection sees outpoint a as locked
+        let _ = utxo_locks.lock_selection(|locked| {
+            assert!(locked.contains(&outpoint_a));
+            Ok::<_, ()>(((), vec![outpoint_b]))
+        });
+
+        // Failed selections do not lock anything
+        let failed = utxo_locks.lock_selection(|_| Err::<((), Vec<OutPoint>), _>("failed"));
+        assert!(failed.is_err());
+
+        assert_eq!(utxo_locks.release(&[outpoint_a]), vec![outpoint_a]);
+        assert_eq!(
+            utxo_locks.locked_outpoints(),
+            HashSet::from_iter(vec![outpoint_b])
+        );
+    }
+
+    #[test]
+    fn test_expired_locks_are_dropped() {
+        let outpoint = OutPoint::from_str(
+            "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2:0",
+        )
+        .unwrap();
+
+        let utxo_locks = UtxoLocks::new(Duration::ZERO);
+
+        let _ = utxo_locks.lock_selection(|_| Ok::<_, ()>(((), vec![outpoint])));
+
+        assert!(utxo_locks.locked_outpoints().is_empty());
+    }
+}
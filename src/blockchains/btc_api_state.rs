@@ -1,14 +1,29 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+
+use futures::{stream, Stream};
+use tracing::error;
 
 use crate::{
     chain::Chain,
+    config::TransactionEventsConfig,
     models::{
-        BroadcastTransactionParams, BroadcastTransactionResponse, CreateTransactionParams,
-        CreateTransactionResponse, NetworkFeeResponse, ValidateTransactionHashResponse,
-        WalletBalanceResponse,
+        AddressInfoResponse, AddressTransactionsResponse, AddressUtxosParams, AddressUtxosResponse,
+        BlockResponse, BlockTxidsResponse, BroadcastTransactionParams,
+        BroadcastTransactionResponse, ConsolidateTransactionParams, ConsolidateTransactionResponse,
+        CreatePaymentUriParams, CreateTransactionParams, CreateTransactionResponse,
+        DecodeTransactionParams, DecodeTransactionResponse, EstimateTransactionFeeParams,
+        EstimateTransactionFeeResponse, MempoolResponse, NetworkFeeParams, NetworkFeeResponse,
+        ParsePaymentUriParams, PaymentUriResponse, ReleaseUtxosParams, ReleaseUtxosResponse,
+        SignMessageParams, SignMessageResponse, SubmitPackageParams, SubmitPackageResponse,
+        TestTransactionParams, TestTransactionResponse, TxnStatus, ValidateTransactionHashResponse,
+        ValidateTransactionHashesParams, ValidateTransactionHashesResponse, VerifyMessageParams,
+        VerifyMessageResponse, WalletBalanceResponse, WalletBalancesParams, WalletBalancesResponse,
     },
 };
 
+// Failed status polls back off up to 2^MAX_BACKOFF_EXPONENT poll intervals
+const MAX_BACKOFF_EXPONENT: u32 = 5;
+
 #[derive(Debug, Clone)]
 // State Abstraction for the blockchain instance
 // All the blockchain specific methods are implemented in the blockchain trait
@@ -16,24 +31,134 @@ use crate::{
 // Todo : Add a type parameter for the blockchain instance, so that we can use the same wrapper for different blockchains.
 pub struct BtcApiState<T: Chain> {
     pub inner: Arc<T>,
+    transaction_events: TransactionEventsConfig,
 }
 
 impl<T: Chain> BtcApiState<T> {
     pub fn new(blockchain: T) -> Self {
         Self {
             inner: Arc::new(blockchain),
+            transaction_events: TransactionEventsConfig::default(),
         }
     }
 
-    pub async fn get_network_fee(&self) -> NetworkFeeResponse {
-        self.inner.get_network_fee().await
+    pub(crate) fn with_transaction_events(
+        mut self,
+        transaction_events: TransactionEventsConfig,
+    ) -> Self {
+        self.transaction_events = transaction_events;
+        self
+    }
+
+    pub async fn get_network_fee(&self, params: NetworkFeeParams) -> NetworkFeeResponse {
+        self.inner.get_network_fee(params).await
+    }
+
+    pub async fn get_mempool(&self) -> MempoolResponse {
+        self.inner.get_mempool().await
+    }
+
+    pub async fn get_block_tip(&self) -> BlockResponse {
+        self.inner.get_block_tip().await
+    }
+
+    pub async fn get_block(&self, hash_or_height: String) -> BlockResponse {
+        self.inner.get_block(hash_or_height).await
+    }
+
+    pub async fn get_block_txids(&self, hash: String) -> BlockTxidsResponse {
+        self.inner.get_block_txids(hash).await
     }
 
     pub async fn validate_transaction_hash(
         &self,
         transaction_hash: String,
+        currency: Option<String>,
     ) -> ValidateTransactionHashResponse {
-        self.inner.validate_transaction_hash(transaction_hash).await
+        self.inner
+            .validate_transaction_hash(transaction_hash, currency)
+            .await
+    }
+
+    pub async fn validate_transaction_hashes(
+        &self,
+        params: ValidateTransactionHashesParams,
+    ) -> ValidateTransactionHashesResponse {
+        self.inner.validate_transaction_hashes(params).await
+    }
+
+    // Polls the transaction status and yields a snapshot every time the status or the
+    // confirmation count changes. The stream ends once the transaction reaches the configured
+    // finality depth or gets cancelled. Failed status checks are logged and retried, backing
+    // off exponentially up to MAX_BACKOFF_EXPONENT doublings of the poll interval. After
+    // max_failed_polls consecutive failures the error is yielded and the stream ends.
+    pub fn transaction_events(
+        &self,
+        transaction_hash: String,
+    ) -> impl Stream<Item = ValidateTransactionHashResponse> + Send + 'static
+    where
+        T: Send + Sync + 'static,
+    {
+        let blockchain = Arc::clone(&self.inner);
+        let finality_depth = self.transaction_events.finality_depth;
+        let poll_interval = Duration::from_secs(self.transaction_events.poll_interval_secs);
+        let max_failed_polls = self.transaction_events.max_failed_polls.max(1);
+
+        // (last emitted status and confirmations, stream finished)
+        let initial_state: (Option<(TxnStatus, Option<u64>)>, bool) = (None, false);
+
+        stream::unfold(initial_state, move |(mut last_seen, finished)| {
+            let blockchain = Arc::clone(&blockchain);
+            let transaction_hash = transaction_hash.clone();
+
+            async move {
+                if finished {
+                    return None;
+                }
+
+                let mut failures = 0;
+                loop {
+                    if last_seen.is_some() || failures > 0 {
+                        let backoff = 2u32.pow(failures.min(MAX_BACKOFF_EXPONENT));
+                        tokio::time::sleep(poll_interval * backoff).await;
+                    }
+
+                    let response = blockchain
+                        .validate_transaction_hash(transaction_hash.clone(), None)
+                        .await;
+
+                    let Some(data) = response.data.as_ref() else {
+                        error!(
+                            "Unable to fetch the status of txn hash {}: {}",
+                            transaction_hash,
+                            response.error_msg.as_deref().unwrap_or_default()
+                        );
+                        failures += 1;
+                        if failures >= max_failed_polls {
+                            return Some((response, (last_seen, true)));
+                        }
+                        continue;
+                    };
+                    failures = 0;
+
+                    let current = (data.txn_status.clone(), data.confirmations);
+                    if last_seen.as_ref() == Some(&current) {
+                        continue;
+                    }
+
+                    let is_final = match data.txn_status {
+                        TxnStatus::Confirmed => data
+                            .confirmations
+                            .is_some_and(|confirmations| confirmations >= finality_depth),
+                        TxnStatus::Cancelled => true,
+                        TxnStatus::Pending => false,
+                    };
+
+                    last_seen = Some(current);
+                    return Some((response, (last_seen, is_final)));
+                }
+            }
+        })
     }
 
     pub async fn create_transaction(
@@ -43,6 +168,20 @@ impl<T: Chain> BtcApiState<T> {
         self.inner.create_transaction(transaction).await
     }
 
+    pub async fn estimate_transaction_fee(
+        &self,
+        transaction: EstimateTransactionFeeParams,
+    ) -> EstimateTransactionFeeResponse {
+        self.inner.estimate_transaction_fee(transaction).await
+    }
+
+    pub async fn consolidate_transaction(
+        &self,
+        consolidation: ConsolidateTransactionParams,
+    ) -> ConsolidateTransactionResponse {
+        self.inner.consolidate_transaction(consolidation).await
+    }
+
     pub async fn broadcast_transaction(
         &self,
         transaction: BroadcastTransactionParams,
@@ -50,7 +189,79 @@ impl<T: Chain> BtcApiState<T> {
         self.inner.broadcast_transaction(transaction).await
     }
 
-    pub async fn get_wallet_balance(&self, address: String) -> WalletBalanceResponse {
-        self.inner.get_wallet_balance(address).await
+    pub async fn decode_transaction(
+        &self,
+        transaction: DecodeTransactionParams,
+    ) -> DecodeTransactionResponse {
+        self.inner.decode_transaction(transaction).await
+    }
+
+    pub async fn submit_package(&self, package: SubmitPackageParams) -> SubmitPackageResponse {
+        self.inner.submit_package(package).await
+    }
+
+    pub async fn test_transaction(
+        &self,
+        transaction: TestTransactionParams,
+    ) -> TestTransactionResponse {
+        self.inner.test_transaction(transaction).await
+    }
+
+    pub async fn release_utxos(&self, release: ReleaseUtxosParams) -> ReleaseUtxosResponse {
+        self.inner.release_utxos(release).await
+    }
+
+    pub async fn parse_payment_uri(&self, params: ParsePaymentUriParams) -> PaymentUriResponse {
+        self.inner.parse_payment_uri(params).await
+    }
+
+    pub async fn create_payment_uri(&self, params: CreatePaymentUriParams) -> PaymentUriResponse {
+        self.inner.create_payment_uri(params).await
+    }
+
+    pub async fn sign_message(&self, params: SignMessageParams) -> SignMessageResponse {
+        self.inner.sign_message(params).await
+    }
+
+    pub async fn verify_message(&self, params: VerifyMessageParams) -> VerifyMessageResponse {
+        self.inner.verify_message(params).await
+    }
+
+    pub async fn get_wallet_balance(
+        &self,
+        address: String,
+        currency: Option<String>,
+    ) -> WalletBalanceResponse {
+        self.inner.get_wallet_balance(address, currency).await
+    }
+
+    pub async fn get_wallet_balances(
+        &self,
+        params: WalletBalancesParams,
+    ) -> WalletBalancesResponse {
+        self.inner.get_wallet_balances(params).await
+    }
+
+    pub async fn get_address_transactions(
+        &self,
+        address: String,
+        last_seen_txid: Option<String>,
+        currency: Option<String>,
+    ) -> AddressTransactionsResponse {
+        self.inner
+            .get_address_transactions(address, last_seen_txid, currency)
+            .await
+    }
+
+    pub async fn get_address_info(&self, address: String) -> AddressInfoResponse {
+        self.inner.get_address_info(address).await
+    }
+
+    pub async fn get_address_utxos(
+        &self,
+        address: String,
+        filters: AddressUtxosParams,
+    ) -> AddressUtxosResponse {
+        self.inner.get_address_utxos(address, filters).await
     }
 }
@@ -1,3 +1,5 @@
+use crate::models::BroadcastErrorCode;
+
 #[derive(Debug)]
 pub enum BtcApiError {
     ConfigLoadError(String),
@@ -14,11 +16,44 @@ pub enum BtcApiError {
     InsufficientFunds(u64),
     RegexError(regex::Error),
     InvalidAddress(String),
+    InvalidTxid(String),
+    UnsupportedAddressType(String),
+    NothingToConsolidate(String),
+    InvalidOutPoint(String),
+    InvalidCoinControl(String),
+    UtxoNotFound(String),
+    UtxoLocked(String),
+    InvalidRawTransaction(String),
+    BroadcastPolicyViolation(String),
+    TransactionRejected(BroadcastErrorCode, String),
+    BitcoindRpcError(bitcoincore_rpc::Error),
+    InvalidPackage(String),
+    InvalidLockTime(String),
+    InvalidOpReturn(String),
+    InvalidPaymentUri(String),
+    InvalidSignature(String),
+    MessageSigningError(String),
+    BlockNotFound(String),
+    PriceFeedError(String),
+    InvalidBatch(String),
+}
+
+impl BtcApiError {
+    // Reason code reported to the caller of a failed broadcast
+    pub fn broadcast_error_code(&self) -> Option<BroadcastErrorCode> {
+        match self {
+            BtcApiError::TransactionRejected(code, _) => Some(code.clone()),
+            BtcApiError::BroadcastPolicyViolation(_) => Some(BroadcastErrorCode::PolicyViolation),
+            BtcApiError::InvalidRawTransaction(_) => Some(BroadcastErrorCode::DecodeFailed),
+            _ => None,
+        }
+    }
 }
 
 impl From<reqwest::Error> for BtcApiError {
     fn from(error: reqwest::Error) -> Self {
-        BtcApiError::ReqwestError(error)
+        // The URL may hold API keys and the error ends up in the API responses
+        BtcApiError::ReqwestError(error.without_url())
     }
 }
 
@@ -46,12 +81,24 @@ impl From<bitcoin::address::ParseError> for BtcApiError {
     }
 }
 
+impl From<bitcoin::consensus::encode::FromHexError> for BtcApiError {
+    fn from(error: bitcoin::consensus::encode::FromHexError) -> Self {
+        BtcApiError::InvalidRawTransaction(error.to_string())
+    }
+}
+
 impl From<url::ParseError> for BtcApiError {
     fn from(error: url::ParseError) -> Self {
         BtcApiError::UrlParseError(error)
     }
 }
 
+impl From<bitcoincore_rpc::Error> for BtcApiError {
+    fn from(error: bitcoincore_rpc::Error) -> Self {
+        BtcApiError::BitcoindRpcError(error)
+    }
+}
+
 impl From<regex::Error> for BtcApiError {
     fn from(error: regex::Error) -> Self {
         BtcApiError::RegexError(error)
@@ -81,6 +128,32 @@ impl std::fmt::Display for BtcApiError {
             }
             BtcApiError::RegexError(e) => write!(f, "RegexError: {}", e),
             BtcApiError::InvalidAddress(address) => write!(f, "InvalidAddress: {}", address),
+            BtcApiError::InvalidTxid(txid) => write!(f, "InvalidTxid: {}", txid),
+            BtcApiError::UnsupportedAddressType(address_type) => {
+                write!(f, "UnsupportedAddressType: {}", address_type)
+            }
+            BtcApiError::NothingToConsolidate(e) => write!(f, "NothingToConsolidate: {}", e),
+            BtcApiError::InvalidOutPoint(outpoint) => write!(f, "InvalidOutPoint: {}", outpoint),
+            BtcApiError::InvalidCoinControl(e) => write!(f, "InvalidCoinControl: {}", e),
+            BtcApiError::UtxoNotFound(outpoint) => write!(f, "UtxoNotFound: {}", outpoint),
+            BtcApiError::UtxoLocked(outpoint) => write!(f, "UtxoLocked: {}", outpoint),
+            BtcApiError::InvalidRawTransaction(e) => write!(f, "InvalidRawTransaction: {}", e),
+            BtcApiError::BroadcastPolicyViolation(e) => {
+                write!(f, "BroadcastPolicyViolation: {}", e)
+            }
+            BtcApiError::TransactionRejected(code, e) => {
+                write!(f, "TransactionRejected ({:?}): {}", code, e)
+            }
+            BtcApiError::BitcoindRpcError(e) => write!(f, "BitcoindRpcError: {}", e),
+            BtcApiError::InvalidPackage(e) => write!(f, "InvalidPackage: {}", e),
+            BtcApiError::InvalidLockTime(e) => write!(f, "InvalidLockTime: {}", e),
+            BtcApiError::InvalidOpReturn(e) => write!(f, "InvalidOpReturn: {}", e),
+            BtcApiError::InvalidPaymentUri(e) => write!(f, "InvalidPaymentUri: {}", e),
+            BtcApiError::InvalidSignature(e) => write!(f, "InvalidSignature: {}", e),
+            BtcApiError::MessageSigningError(e) => write!(f, "MessageSigningError: {}", e),
+            BtcApiError::BlockNotFound(block) => write!(f, "BlockNotFound: {}", block),
+            BtcApiError::PriceFeedError(e) => write!(f, "PriceFeedError: {}", e),
+            BtcApiError::InvalidBatch(e) => write!(f, "InvalidBatch: {}", e),
         }
     }
 }
@@ -1,9 +1,17 @@
 use serde::Deserialize;
 
 use crate::models::{
-    BroadcastTransactionParams, BroadcastTransactionResponse, CreateTransactionParams,
-    CreateTransactionResponse, NetworkFeeResponse, ValidateTransactionHashResponse,
-    WalletBalanceResponse,
+    AddressInfoResponse, AddressTransactionsResponse, AddressUtxosParams, AddressUtxosResponse,
+    BlockResponse, BlockTxidsResponse, BroadcastTransactionParams, BroadcastTransactionResponse,
+    ConsolidateTransactionParams, ConsolidateTransactionResponse, CreatePaymentUriParams,
+    CreateTransactionParams, CreateTransactionResponse, DecodeTransactionParams,
+    DecodeTransactionResponse, EstimateTransactionFeeParams, EstimateTransactionFeeResponse,
+    MempoolResponse, NetworkFeeParams, NetworkFeeResponse, ParsePaymentUriParams,
+    PaymentUriResponse, ReleaseUtxosParams, ReleaseUtxosResponse, SignMessageParams,
+    SignMessageResponse, SubmitPackageParams, SubmitPackageResponse, TestTransactionParams,
+    TestTransactionResponse, ValidateTransactionHashResponse, ValidateTransactionHashesParams,
+    ValidateTransactionHashesResponse, VerifyMessageParams, VerifyMessageResponse,
+    WalletBalanceResponse, WalletBalancesParams, WalletBalancesResponse,
 };
 
 #[derive(Deserialize, Debug)]
@@ -17,18 +25,64 @@ pub(crate) enum ChainName {
 #[async_trait::async_trait]
 #[mockall::automock]
 pub trait Chain {
-    async fn get_network_fee(&self) -> NetworkFeeResponse;
+    async fn get_network_fee(&self, params: NetworkFeeParams) -> NetworkFeeResponse;
+    async fn get_mempool(&self) -> MempoolResponse;
+    async fn get_block_tip(&self) -> BlockResponse;
+    async fn get_block(&self, hash_or_height: String) -> BlockResponse;
+    async fn get_block_txids(&self, hash: String) -> BlockTxidsResponse;
     async fn validate_transaction_hash(
         &self,
         transaction_hash: String,
+        currency: Option<String>,
     ) -> ValidateTransactionHashResponse;
+    async fn validate_transaction_hashes(
+        &self,
+        params: ValidateTransactionHashesParams,
+    ) -> ValidateTransactionHashesResponse;
     async fn create_transaction(
         &self,
         transaction: CreateTransactionParams,
     ) -> CreateTransactionResponse;
+    async fn estimate_transaction_fee(
+        &self,
+        transaction: EstimateTransactionFeeParams,
+    ) -> EstimateTransactionFeeResponse;
+    async fn consolidate_transaction(
+        &self,
+        consolidation: ConsolidateTransactionParams,
+    ) -> ConsolidateTransactionResponse;
     async fn broadcast_transaction(
         &self,
         transaction: BroadcastTransactionParams,
     ) -> BroadcastTransactionResponse;
-    async fn get_wallet_balance(&self, address: String) -> WalletBalanceResponse;
+    async fn decode_transaction(
+        &self,
+        transaction: DecodeTransactionParams,
+    ) -> DecodeTransactionResponse;
+    async fn submit_package(&self, package: SubmitPackageParams) -> SubmitPackageResponse;
+    async fn test_transaction(&self, transaction: TestTransactionParams)
+        -> TestTransactionResponse;
+    async fn release_utxos(&self, release: ReleaseUtxosParams) -> ReleaseUtxosResponse;
+    async fn parse_payment_uri(&self, params: ParsePaymentUriParams) -> PaymentUriResponse;
+    async fn create_payment_uri(&self, params: CreatePaymentUriParams) -> PaymentUriResponse;
+    async fn sign_message(&self, params: SignMessageParams) -> SignMessageResponse;
+    async fn verify_message(&self, params: VerifyMessageParams) -> VerifyMessageResponse;
+    async fn get_wallet_balance(
+        &self,
+        address: String,
+        currency: Option<String>,
+    ) -> WalletBalanceResponse;
+    async fn get_wallet_balances(&self, params: WalletBalancesParams) -> WalletBalancesResponse;
+    async fn get_address_transactions(
+        &self,
+        address: String,
+        last_seen_txid: Option<String>,
+        currency: Option<String>,
+    ) -> AddressTransactionsResponse;
+    async fn get_address_info(&self, address: String) -> AddressInfoResponse;
+    async fn get_address_utxos(
+        &self,
+        address: String,
+        filters: AddressUtxosParams,
+    ) -> AddressUtxosResponse;
 }
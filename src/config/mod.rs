@@ -18,17 +18,217 @@ pub(crate) struct Config {
     pub rust_log_level: Level,
     #[serde(default)]
     pub sign_txn: bool,
+    #[serde(default)]
+    pub transaction_events: TransactionEventsConfig,
 }
 
 fn default_log_level() -> Level {
     Level::INFO
 }
 
+// Settings for the /transactions/{txid}/events SSE stream
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct TransactionEventsConfig {
+    // Number of confirmations after which the stream is closed
+    #[serde(default = "default_finality_depth")]
+    pub finality_depth: u64,
+    // How often the transaction status is polled from the chain
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    // Consecutive failed polls after which an error event is sent and the stream is closed
+    #[serde(default = "default_max_failed_polls")]
+    pub max_failed_polls: u32,
+}
+
+fn default_finality_depth() -> u64 {
+    6
+}
+
+fn default_poll_interval_secs() -> u64 {
+    30
+}
+
+fn default_max_failed_polls() -> u32 {
+    5
+}
+
+impl Default for TransactionEventsConfig {
+    fn default() -> Self {
+        Self {
+            finality_depth: default_finality_depth(),
+            poll_interval_secs: default_poll_interval_secs(),
+            max_failed_polls: default_max_failed_polls(),
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub(crate) struct ChainConfig {
     pub chain: ChainName,
     pub rpc_url: String,
     pub variant: ChainVariant,
+    // How long UTXOs selected by createTransaction/consolidate stay reserved
+    #[serde(default = "default_utxo_lock_ttl_secs")]
+    pub utxo_lock_ttl_secs: u64,
+    #[serde(default)]
+    pub broadcast_policy: BroadcastPolicyConfig,
+    // Endpoints a signed transaction is broadcasted to concurrently, rpc_url when empty
+    #[serde(default)]
+    pub broadcast_providers: Vec<BroadcastProviderConfig>,
+    // Optional bitcoind node, required by the bitcoind broadcast provider and fee source
+    pub bitcoind: Option<BitcoindConfig>,
+    #[serde(default)]
+    pub fee_estimation: FeeEstimationConfig,
+    // Fiat prices of the currency query parameter, mempool.space when not set
+    pub price_feed: Option<PriceFeedConfig>,
+    #[serde(default)]
+    pub batch_requests: BatchRequestsConfig,
+}
+
+fn default_utxo_lock_ttl_secs() -> u64 {
+    600
+}
+
+// Local checks run before broadcasting a transaction, unless the broadcast is forced
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct BroadcastPolicyConfig {
+    // In sat/vB
+    #[serde(default = "default_max_fee_rate")]
+    pub max_fee_rate: f64,
+    // Maximum fee as a percentage of the amount sent by the transaction, change excluded
+    #[serde(default = "default_max_fee_percent")]
+    pub max_fee_percent: f64,
+}
+
+fn default_max_fee_rate() -> f64 {
+    1000.0
+}
+
+fn default_max_fee_percent() -> f64 {
+    10.0
+}
+
+impl Default for BroadcastPolicyConfig {
+    fn default() -> Self {
+        Self {
+            max_fee_rate: default_max_fee_rate(),
+            max_fee_percent: default_max_fee_percent(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(crate) enum BroadcastProviderConfig {
+    // Esplora compatible REST API, e.g. https://mempool.space/testnet/api/
+    Esplora { url: String },
+    // The node configured in bitcoind
+    Bitcoind,
+}
+
+// Sources of /networkFee, aggregated by median after dropping outliers
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct FeeEstimationConfig {
+    // mempool.space of the network and rpc_url when empty
+    #[serde(default)]
+    pub sources: Vec<FeeSourceConfig>,
+    #[serde(default = "default_fee_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    // In sat/vB, no fee rate is reported below it
+    #[serde(default = "default_min_fee_rate")]
+    pub min_fee_rate: f64,
+    // Rates more than this many times above or below the median of the sources are dropped
+    #[serde(default = "default_max_fee_deviation")]
+    pub max_deviation: f64,
+}
+
+fn default_fee_cache_ttl_secs() -> u64 {
+    60
+}
+
+fn default_min_fee_rate() -> f64 {
+    1.0
+}
+
+fn default_max_fee_deviation() -> f64 {
+    3.0
+}
+
+impl Default for FeeEstimationConfig {
+    fn default() -> Self {
+        Self {
+            sources: vec![],
+            cache_ttl_secs: default_fee_cache_ttl_secs(),
+            min_fee_rate: default_min_fee_rate(),
+            max_deviation: default_max_fee_deviation(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(crate) enum FeeSourceConfig {
+    // mempool.space compatible REST API, e.g. https://mempool.space/testnet/api/
+    MempoolSpace { url: String },
+    // Esplora compatible REST API
+    Esplora { url: String },
+    // estimatesmartfee of the node configured in bitcoind
+    Bitcoind,
+}
+
+// Limits of the batch endpoints, e.g. /walletBalances
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct BatchRequestsConfig {
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+    // Items of a batch fetched from the provider at the same time
+    #[serde(default = "default_max_batch_concurrency")]
+    pub max_concurrency: usize,
+}
+
+fn default_max_batch_size() -> usize {
+    100
+}
+
+fn default_max_batch_concurrency() -> usize {
+    8
+}
+
+impl Default for BatchRequestsConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: default_max_batch_size(),
+            max_concurrency: default_max_batch_concurrency(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(crate) enum PriceFeedConfig {
+    // JSON file of currency to [timestamp, price] pairs, for offline use
+    File { path: String },
+    // mempool.space compatible REST API, e.g. https://mempool.space/api/
+    Http { url: String },
+}
+
+// JSON-RPC connection to a bitcoind node
+#[derive(Deserialize, Clone)]
+pub(crate) struct BitcoindConfig {
+    pub url: String,
+    pub user: String,
+    pub password: String,
+}
+
+// Keeps the rpc password out of the logged config
+impl std::fmt::Debug for BitcoindConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BitcoindConfig")
+            .field("url", &self.url)
+            .field("user", &self.user)
+            .field("password", &"***")
+            .finish()
+    }
 }
 
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -1,17 +1,30 @@
+use std::convert::Infallible;
+
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
+    response::sse::{Event, KeepAlive, Sse},
     Json,
 };
+use futures::{Stream, StreamExt};
 use tracing::{debug, error};
 
 use crate::{
     blockchains::btc_api_state::BtcApiState,
     chain::Chain,
     models::{
-        BroadcastTransactionParams, BroadcastTransactionResponse, CreateTransactionParams,
-        CreateTransactionResponse, MethodNotAllowedResponse, NetworkFeeResponse,
-        ValidateTransactionHashParams, ValidateTransactionHashResponse, WalletBalanceParams,
-        WalletBalanceResponse,
+        AddressInfoResponse, AddressTransactionsParams, AddressTransactionsResponse,
+        AddressUtxosParams, AddressUtxosResponse, BlockResponse, BlockTxidsResponse,
+        BroadcastTransactionParams, BroadcastTransactionResponse, ConsolidateTransactionParams,
+        ConsolidateTransactionResponse, CreatePaymentUriParams, CreateTransactionParams,
+        CreateTransactionResponse, DecodeTransactionParams, DecodeTransactionResponse,
+        EstimateTransactionFeeParams, EstimateTransactionFeeResponse, MempoolResponse,
+        MethodNotAllowedResponse, NetworkFeeParams, NetworkFeeResponse, ParsePaymentUriParams,
+        PaymentUriResponse, ReleaseUtxosParams, ReleaseUtxosResponse, SignMessageParams,
+        SignMessageResponse, SubmitPackageParams, SubmitPackageResponse, TestTransactionParams,
+        TestTransactionResponse, ValidateTransactionHashParams, ValidateTransactionHashResponse,
+        ValidateTransactionHashesParams, ValidateTransactionHashesResponse, VerifyMessageParams,
+        VerifyMessageResponse, WalletBalanceParams, WalletBalanceResponse, WalletBalancesParams,
+        WalletBalancesResponse,
     },
 };
 
@@ -26,10 +39,41 @@ pub(crate) async fn method_not_allowed_handler<T: Chain>(
 }
 
 pub(crate) async fn bitcoin_network_fee_handler<T: Chain>(
+    Query(params): Query<NetworkFeeParams>,
     State(blockchain): State<BtcApiState<T>>,
 ) -> Json<NetworkFeeResponse> {
-    debug!("Received request to get network fee");
-    Json(blockchain.get_network_fee().await)
+    debug!("Received request to get network fee: {:#?}", params);
+    Json(blockchain.get_network_fee(params).await)
+}
+
+pub(crate) async fn bitcoin_mempool_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+) -> Json<MempoolResponse> {
+    debug!("Received request to get mempool statistics");
+    Json(blockchain.get_mempool().await)
+}
+
+pub(crate) async fn bitcoin_block_tip_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+) -> Json<BlockResponse> {
+    debug!("Received request to get the tip block");
+    Json(blockchain.get_block_tip().await)
+}
+
+pub(crate) async fn bitcoin_block_handler<T: Chain>(
+    Path(hash_or_height): Path<String>,
+    State(blockchain): State<BtcApiState<T>>,
+) -> Json<BlockResponse> {
+    debug!("Received request to get block: {}", hash_or_height);
+    Json(blockchain.get_block(hash_or_height).await)
+}
+
+pub(crate) async fn bitcoin_block_txids_handler<T: Chain>(
+    Path(hash): Path<String>,
+    State(blockchain): State<BtcApiState<T>>,
+) -> Json<BlockTxidsResponse> {
+    debug!("Received request to get the txids of block: {}", hash);
+    Json(blockchain.get_block_txids(hash).await)
 }
 
 pub(crate) async fn bitcoin_validate_transaction_hash_handler<T: Chain>(
@@ -41,11 +85,78 @@ pub(crate) async fn bitcoin_validate_transaction_hash_handler<T: Chain>(
         params
     );
 
-    Json(
-        blockchain
-            .validate_transaction_hash(params.transaction_hash)
-            .await,
-    )
+    let mut response = blockchain
+        .validate_transaction_hash(params.transaction_hash, params.currency)
+        .await;
+
+    if params.include_btc {
+        if let Some(txn_data) = response
+            .data
+            .as_mut()
+            .and_then(|data| data.txn_data.as_mut())
+        {
+            txn_data.add_btc_amounts();
+        }
+    }
+
+    Json(response)
+}
+
+pub(crate) async fn bitcoin_validate_transaction_hashes_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<ValidateTransactionHashesParams>,
+) -> Json<ValidateTransactionHashesResponse> {
+    debug!(
+        "Received request to validate transaction hashes: {:#?}",
+        params
+    );
+
+    let include_btc = params.include_btc;
+    let mut response = blockchain.validate_transaction_hashes(params).await;
+
+    if include_btc {
+        for transaction in response
+            .data
+            .iter_mut()
+            .flat_map(|data| &mut data.transactions)
+        {
+            if let Some(txn_data) = transaction
+                .data
+                .as_mut()
+                .and_then(|data| data.txn_data.as_mut())
+            {
+                txn_data.add_btc_amounts();
+            }
+        }
+    }
+
+    Json(response)
+}
+
+pub(crate) async fn bitcoin_transaction_events_handler<T: Chain + Send + Sync + 'static>(
+    Path(transaction_hash): Path<String>,
+    State(blockchain): State<BtcApiState<T>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    debug!(
+        "Received request to stream transaction events: {}",
+        transaction_hash
+    );
+
+    let events = blockchain
+        .transaction_events(transaction_hash)
+        .map(|response| {
+            let event = match response.data {
+                Some(data) => Event::default().event("status").json_data(data),
+                None => Event::default().event("error").json_data(response),
+            };
+
+            Ok(event.unwrap_or_else(|err| {
+                error!("Unable to serialize transaction event: {}", err);
+                Event::default().event("error").data(err.to_string())
+            }))
+        });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
 }
 
 pub(crate) async fn bitcoin_wallet_balance_handler<T: Chain>(
@@ -54,7 +165,167 @@ pub(crate) async fn bitcoin_wallet_balance_handler<T: Chain>(
 ) -> Json<WalletBalanceResponse> {
     debug!("Received request to fetch wallet balance: {:#?}", params);
 
-    Json(blockchain.get_wallet_balance(params.wallet_address).await)
+    let mut response = blockchain
+        .get_wallet_balance(params.wallet_address, params.currency)
+        .await;
+
+    if params.include_btc {
+        if let Some(wallet_balance) = response.data.as_mut() {
+            wallet_balance.add_btc_amounts();
+        }
+    }
+
+    Json(response)
+}
+
+pub(crate) async fn bitcoin_wallet_balances_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<WalletBalancesParams>,
+) -> Json<WalletBalancesResponse> {
+    debug!("Received request to fetch wallet balances: {:#?}", params);
+
+    let include_btc = params.include_btc;
+    let mut response = blockchain.get_wallet_balances(params).await;
+
+    if include_btc {
+        for balance in response.data.iter_mut().flat_map(|data| &mut data.balances) {
+            if let Some(wallet_balance) = balance.data.as_mut() {
+                wallet_balance.add_btc_amounts();
+            }
+        }
+    }
+
+    Json(response)
+}
+
+pub(crate) async fn bitcoin_decode_transaction_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<DecodeTransactionParams>,
+) -> Json<DecodeTransactionResponse> {
+    debug!("Received request to decode transaction: {:#?}", params);
+
+    Json(blockchain.decode_transaction(params).await)
+}
+
+pub(crate) async fn bitcoin_submit_package_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<SubmitPackageParams>,
+) -> Json<SubmitPackageResponse> {
+    debug!("Received request to submit package: {:#?}", params);
+
+    Json(blockchain.submit_package(params).await)
+}
+
+pub(crate) async fn bitcoin_test_transaction_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<TestTransactionParams>,
+) -> Json<TestTransactionResponse> {
+    debug!("Received request to test transaction: {:#?}", params);
+
+    Json(blockchain.test_transaction(params).await)
+}
+
+pub(crate) async fn bitcoin_parse_payment_uri_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<ParsePaymentUriParams>,
+) -> Json<PaymentUriResponse> {
+    debug!("Received request to parse payment URI: {:#?}", params);
+
+    Json(blockchain.parse_payment_uri(params).await)
+}
+
+pub(crate) async fn bitcoin_create_payment_uri_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<CreatePaymentUriParams>,
+) -> Json<PaymentUriResponse> {
+    debug!("Received request to create payment URI: {:#?}", params);
+
+    Json(blockchain.create_payment_uri(params).await)
+}
+
+pub(crate) async fn bitcoin_sign_message_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<SignMessageParams>,
+) -> Json<SignMessageResponse> {
+    debug!("Received request to sign message: {:#?}", params);
+
+    Json(blockchain.sign_message(params).await)
+}
+
+pub(crate) async fn bitcoin_verify_message_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<VerifyMessageParams>,
+) -> Json<VerifyMessageResponse> {
+    debug!("Received request to verify message: {:#?}", params);
+
+    Json(blockchain.verify_message(params).await)
+}
+
+pub(crate) async fn bitcoin_release_utxos_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<ReleaseUtxosParams>,
+) -> Json<ReleaseUtxosResponse> {
+    debug!("Received request to release UTXOs: {:#?}", params);
+
+    Json(blockchain.release_utxos(params).await)
+}
+
+pub(crate) async fn bitcoin_address_transactions_handler<T: Chain>(
+    Path(address): Path<String>,
+    Query(params): Query<AddressTransactionsParams>,
+    State(blockchain): State<BtcApiState<T>>,
+) -> Json<AddressTransactionsResponse> {
+    debug!(
+        "Received request to fetch transactions of address {}: {:#?}",
+        address, params
+    );
+
+    let mut response = blockchain
+        .get_address_transactions(address, params.last_seen_txid, params.currency)
+        .await;
+
+    if params.include_btc {
+        for transaction in response
+            .data
+            .iter_mut()
+            .flat_map(|data| &mut data.transactions)
+        {
+            transaction.add_btc_amounts();
+        }
+    }
+
+    Json(response)
+}
+
+pub(crate) async fn bitcoin_address_info_handler<T: Chain>(
+    Path(address): Path<String>,
+    State(blockchain): State<BtcApiState<T>>,
+) -> Json<AddressInfoResponse> {
+    debug!("Received request to describe address {}", address);
+
+    Json(blockchain.get_address_info(address).await)
+}
+
+pub(crate) async fn bitcoin_address_utxos_handler<T: Chain>(
+    Path(address): Path<String>,
+    Query(params): Query<AddressUtxosParams>,
+    State(blockchain): State<BtcApiState<T>>,
+) -> Json<AddressUtxosResponse> {
+    debug!(
+        "Received request to fetch UTXOs of address {}: {:#?}",
+        address, params
+    );
+
+    let include_btc = params.include_btc;
+    let mut response = blockchain.get_address_utxos(address, params).await;
+
+    if include_btc {
+        if let Some(address_utxos) = response.data.as_mut() {
+            address_utxos.add_btc_amounts();
+        }
+    }
+
+    Json(response)
 }
 
 pub(crate) async fn bitcoin_create_transaction_handler<T: Chain>(
@@ -63,14 +334,53 @@ pub(crate) async fn bitcoin_create_transaction_handler<T: Chain>(
 ) -> Json<CreateTransactionResponse> {
     debug!("Received request to create transaction: {:#?}", params);
 
-    match params.validate() {
-        Ok(params) => Json(blockchain.create_transaction(params).await),
-        Err(e) => Json(CreateTransactionResponse {
+    let include_btc = params.include_btc;
+    let mut response = match params.validate() {
+        Ok(params) => blockchain.create_transaction(params).await,
+        Err(e) => CreateTransactionResponse {
             is_error: true,
             data: None,
             error_msg: Some(e.to_string()),
-        }),
+        },
+    };
+
+    if include_btc {
+        if let Some(created_transaction) = response.data.as_mut() {
+            created_transaction.add_btc_amounts();
+        }
     }
+
+    Json(response)
+}
+
+pub(crate) async fn bitcoin_estimate_transaction_fee_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<EstimateTransactionFeeParams>,
+) -> Json<EstimateTransactionFeeResponse> {
+    debug!(
+        "Received request to estimate transaction fee: {:#?}",
+        params
+    );
+
+    Json(blockchain.estimate_transaction_fee(params).await)
+}
+
+pub(crate) async fn bitcoin_consolidate_transaction_handler<T: Chain>(
+    State(blockchain): State<BtcApiState<T>>,
+    Json(params): Json<ConsolidateTransactionParams>,
+) -> Json<ConsolidateTransactionResponse> {
+    debug!("Received request to consolidate UTXOs: {:#?}", params);
+
+    let include_btc = params.include_btc;
+    let mut response = blockchain.consolidate_transaction(params).await;
+
+    if include_btc {
+        if let Some(consolidation) = response.data.as_mut() {
+            consolidation.add_btc_amounts();
+        }
+    }
+
+    Json(response)
 }
 
 pub(crate) async fn bitcoin_broadcast_transaction_handler<T: Chain>(
@@ -112,11 +422,14 @@ mod tests {
         let expected_network_fee_response = NetworkFeeResponse {
             is_error: false,
             data: Some(NetworkFeeResponseData {
-                fastest_fee: 100,
-                half_hour_fee: 90,
-                hour_fee: 80,
-                economy_fee: 70,
-                minimum_fee: 60,
+                fastest_fee: 100.0,
+                half_hour_fee: 90.0,
+                hour_fee: 80.0,
+                economy_fee: 70.0,
+                minimum_fee: 60.0,
+                sources: vec!["mempoolSpace(mempool.space)".to_string()],
+                fallback: None,
+                target: None,
             }),
             error_msg: None,
         };
@@ -124,7 +437,7 @@ mod tests {
         let expected_network_fee_response_clone = expected_network_fee_response.clone();
 
         let mut mock_bitcoin = MockChain::new();
-        mock_bitcoin.expect_get_network_fee().returning(move || {
+        mock_bitcoin.expect_get_network_fee().returning(move |_| {
             let expected_network_fee_response = expected_network_fee_response.clone();
             Box::pin(async { expected_network_fee_response })
         });
@@ -150,4 +463,267 @@ mod tests {
 
         assert_eq!(de_body, expected_network_fee_response_clone)
     }
+
+    /// Tests the transaction events handler by feeding a pending, a repeated and two confirmed
+    /// snapshots and verifying that only changes are emitted and the stream closes at finality.
+    #[tokio::test]
+    async fn test_transaction_events_handler() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+
+        use crate::chain::MockChain;
+        use crate::config::TransactionEventsConfig;
+        use crate::models::{TxnStatus, ValidateTransactionHashResponseData};
+        use axum::routing::get;
+        use axum::{
+            body::Body,
+            http::{Request, StatusCode},
+            Router,
+        };
+        use tower::ServiceExt; // for `oneshot`
+
+        let polls = Arc::new(AtomicU64::new(0));
+
+        let mut mock_bitcoin = MockChain::new();
+        mock_bitcoin
+            .expect_validate_transaction_hash()
+            .returning(move |txn_hash, _| {
+                let poll = polls.fetch_add(1, Ordering::SeqCst);
+                let (txn_status, txn_status_flag, confirmations) = match poll {
+                    0..=2 => (TxnStatus::Pending, 2, Some(0)),
+                    3 => (TxnStatus::Confirmed, 0, None),
+                    4 => (TxnStatus::Confirmed, 0, Some(1)),
+                    _ => (TxnStatus::Confirmed, 0, Some(2)),
+                };
+
+                // A failed poll does not end the stream
+                if poll == 1 {
+                    return Box::pin(async move {
+                        ValidateTransactionHashResponse {
+                            is_error: true,
+                            data: None,
+                            error_msg: Some("ExternalApiError: unavailable".to_string()),
+                        }
+                    });
+                }
+
+                Box::pin(async move {
+                    ValidateTransactionHashResponse {
+                        is_error: false,
+                        data: Some(ValidateTransactionHashResponseData {
+                            txn_hash,
+                            txn_status,
+                            txn_status_flag,
+                            confirmations,
+                            txn_data: None,
+                        }),
+                        error_msg: None,
+                    }
+                })
+            });
+
+        let state =
+            BtcApiState::new(mock_bitcoin).with_transaction_events(TransactionEventsConfig {
+                finality_depth: 2,
+                poll_interval_secs: 0,
+                max_failed_polls: 2,
+            });
+
+        let app = Router::new()
+            .route(
+                "/transactions/{txid}/events",
+                get(bitcoin_transaction_events_handler),
+            )
+            .with_state(state);
+
+        let request = Request::builder()
+            .uri("/transactions/abcd/events")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap();
+        let body_str = String::from_utf8(body.to_bytes().to_vec()).unwrap();
+
+        let snapshots = body_str
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str::<ValidateTransactionHashResponseData>(data).unwrap())
+            .map(|data| (data.txn_status, data.confirmations))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            snapshots,
+            vec![
+                (TxnStatus::Pending, Some(0)),
+                (TxnStatus::Confirmed, None),
+                (TxnStatus::Confirmed, Some(1)),
+                (TxnStatus::Confirmed, Some(2))
+            ]
+        );
+    }
+
+    /// Tests that the transaction events stream sends a single error event and closes once the
+    /// status polls keep failing, instead of retrying forever.
+    #[tokio::test]
+    async fn test_transaction_events_handler_failures() {
+        use crate::chain::MockChain;
+        use crate::config::TransactionEventsConfig;
+        use axum::routing::get;
+        use axum::{
+            body::Body,
+            http::{Request, StatusCode},
+            Router,
+        };
+        use tower::ServiceExt; // for `oneshot`
+
+        let mut mock_bitcoin = MockChain::new();
+        mock_bitcoin
+            .expect_validate_transaction_hash()
+            .times(3)
+            .returning(|_, _| {
+                Box::pin(async move {
+                    ValidateTransactionHashResponse {
+                        is_error: true,
+                        data: None,
+                        error_msg: Some("InvalidTxid: abcd".to_string()),
+                    }
+                })
+            });
+
+        let state =
+            BtcApiState::new(mock_bitcoin).with_transaction_events(TransactionEventsConfig {
+                finality_depth: 2,
+                poll_interval_secs: 0,
+                max_failed_polls: 3,
+            });
+
+        let app = Router::new()
+            .route(
+                "/transactions/{txid}/events",
+                get(bitcoin_transaction_events_handler),
+            )
+            .with_state(state);
+
+        let request = Request::builder()
+            .uri("/transactions/abcd/events")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Collecting the body only returns once the stream is closed
+        let body = response.into_body().collect().await.unwrap();
+        let body_str = String::from_utf8(body.to_bytes().to_vec()).unwrap();
+
+        let events = body_str
+            .lines()
+            .filter_map(|line| line.strip_prefix("event: "))
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec!["error"]);
+
+        let error = body_str
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str::<ValidateTransactionHashResponse>(data).unwrap())
+            .unwrap();
+        assert!(error.is_error);
+        assert_eq!(error.error_msg.as_deref(), Some("InvalidTxid: abcd"));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_balances_handler() {
+        use crate::chain::MockChain;
+        use crate::models::{
+            AddressWalletBalance, WalletBalanceResponseData, WalletBalancesResponseData,
+        };
+        use axum::routing::post;
+        use axum::{
+            body::Body,
+            http::{Request, StatusCode},
+            Router,
+        };
+        use bitcoin::SignedAmount;
+        use tower::ServiceExt; // for `oneshot`
+
+        let mut mock_bitcoin = MockChain::new();
+        mock_bitcoin
+            .expect_get_wallet_balances()
+            .returning(|params| {
+                let balances = params
+                    .wallet_addresses
+                    .into_iter()
+                    .map(|wallet_address| match wallet_address.as_str() {
+                        "invalid" => AddressWalletBalance {
+                            wallet_address,
+                            is_error: true,
+                            data: None,
+                            error_msg: Some("InvalidAddress: invalid".to_string()),
+                        },
+                        _ => AddressWalletBalance {
+                            wallet_address,
+                            is_error: false,
+                            data: Some(WalletBalanceResponseData {
+                                confirmed_balance: SignedAmount::from_sat(150000),
+                                unconfirmed_balance: SignedAmount::ZERO,
+                                total_balance: SignedAmount::from_sat(150000),
+                                fiat: None,
+                                btc: None,
+                            }),
+                            error_msg: None,
+                        },
+                    })
+                    .collect();
+
+                Box::pin(async move {
+                    WalletBalancesResponse {
+                        is_error: false,
+                        data: Some(WalletBalancesResponseData { balances }),
+                        error_msg: None,
+                    }
+                })
+            });
+
+        let app = Router::new()
+            .route("/walletBalances", post(bitcoin_wallet_balances_handler))
+            .with_state(BtcApiState::new(mock_bitcoin));
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/walletBalances")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"wallet_addresses": ["tb1q4a0umk5zxq302kfzqg5qfxta5jcp46ws6r0pjy", "invalid"], "include_btc": true}"#,
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap();
+        let response: WalletBalancesResponse = serde_json::from_slice(&body.to_bytes()).unwrap();
+        let balances = response.data.unwrap().balances;
+
+        // One failed address does not fail the batch
+        assert_eq!(balances.len(), 2);
+        assert_eq!(
+            balances[0]
+                .data
+                .as_ref()
+                .unwrap()
+                .btc
+                .as_ref()
+                .unwrap()
+                .total_balance,
+            "0.0015"
+        );
+        assert!(balances[1].is_error);
+        assert!(balances[1].data.is_none());
+    }
 }
@@ -6,11 +6,21 @@ use blockchains::bitcoin::Bitcoin;
 use btc_api_error::BtcApiError;
 use chain::ChainName;
 use handlers::{
-    bitcoin_broadcast_transaction_handler, bitcoin_create_transaction_handler,
-    bitcoin_network_fee_handler, bitcoin_validate_transaction_hash_handler,
-    bitcoin_wallet_balance_handler, method_not_allowed_handler,
+    bitcoin_address_info_handler, bitcoin_address_transactions_handler,
+    bitcoin_address_utxos_handler, bitcoin_block_handler, bitcoin_block_tip_handler,
+    bitcoin_block_txids_handler, bitcoin_broadcast_transaction_handler,
+    bitcoin_consolidate_transaction_handler, bitcoin_create_payment_uri_handler,
+    bitcoin_create_transaction_handler, bitcoin_decode_transaction_handler,
+    bitcoin_estimate_transaction_fee_handler, bitcoin_mempool_handler, bitcoin_network_fee_handler,
+    bitcoin_parse_payment_uri_handler, bitcoin_release_utxos_handler, bitcoin_sign_message_handler,
+    bitcoin_submit_package_handler, bitcoin_test_transaction_handler,
+    bitcoin_transaction_events_handler, bitcoin_validate_transaction_hash_handler,
+    bitcoin_validate_transaction_hashes_handler, bitcoin_verify_message_handler,
+    bitcoin_wallet_balance_handler, bitcoin_wallet_balances_handler, method_not_allowed_handler,
 };
 
+use std::time::Duration;
+
 use tower_http::services::{ServeDir, ServeFile};
 use tracing::info;
 
@@ -44,28 +54,82 @@ async fn main() -> Result<(), BtcApiError> {
     // Create shared state of the blockchain instance
     let blockchain = match config.chain_config.chain {
         //Should inject the required config into the blockchain instance here.
-        ChainName::Bitcoin => BtcApiState::new(Bitcoin::new(
-            &config.chain_config.rpc_url,
-            &config.chain_config.variant,
-            config.sign_txn,
-        )?),
+        ChainName::Bitcoin => BtcApiState::new(
+            Bitcoin::new(
+                &config.chain_config.rpc_url,
+                &config.chain_config.variant,
+                config.sign_txn,
+            )?
+            .with_utxo_lock_ttl(Duration::from_secs(config.chain_config.utxo_lock_ttl_secs))
+            .with_broadcast_policy(config.chain_config.broadcast_policy.clone())
+            .with_batch_requests(config.chain_config.batch_requests.clone())
+            .with_bitcoind(config.chain_config.bitcoind.as_ref())?
+            .with_broadcast_providers(&config.chain_config.broadcast_providers)?
+            .with_fee_estimation(&config.chain_config.fee_estimation)?
+            .with_price_feed(config.chain_config.price_feed.as_ref())?,
+        )
+        .with_transaction_events(config.transaction_events.clone()),
     };
 
     let app = Router::new()
         .route("/networkFee", get(bitcoin_network_fee_handler))
+        .route("/mempool", get(bitcoin_mempool_handler))
+        .route("/blocks/tip", get(bitcoin_block_tip_handler))
+        .route("/blocks/{hash_or_height}", get(bitcoin_block_handler))
+        .route("/blocks/{hash}/txids", get(bitcoin_block_txids_handler))
         .route(
             "/validateTransactionHash",
             get(bitcoin_validate_transaction_hash_handler),
         )
+        .route(
+            "/validateTransactionHashes",
+            post(bitcoin_validate_transaction_hashes_handler),
+        )
+        .route(
+            "/transactions/{txid}/events",
+            get(bitcoin_transaction_events_handler),
+        )
         .route(
             "/createTransaction",
             post(bitcoin_create_transaction_handler),
         )
+        .route(
+            "/estimateTransactionFee",
+            post(bitcoin_estimate_transaction_fee_handler),
+        )
+        .route(
+            "/consolidate",
+            post(bitcoin_consolidate_transaction_handler),
+        )
+        .route("/releaseUtxos", post(bitcoin_release_utxos_handler))
+        .route(
+            "/decodeTransaction",
+            post(bitcoin_decode_transaction_handler),
+        )
+        .route("/testTransaction", post(bitcoin_test_transaction_handler))
+        .route("/submitPackage", post(bitcoin_submit_package_handler))
         .route(
             "/broadcastTransaction",
             post(bitcoin_broadcast_transaction_handler),
         )
+        .route("/paymentUri/parse", post(bitcoin_parse_payment_uri_handler))
+        .route(
+            "/paymentUri/create",
+            post(bitcoin_create_payment_uri_handler),
+        )
+        .route("/message/sign", post(bitcoin_sign_message_handler))
+        .route("/message/verify", post(bitcoin_verify_message_handler))
         .route("/walletBalance", get(bitcoin_wallet_balance_handler))
+        .route("/walletBalances", post(bitcoin_wallet_balances_handler))
+        .route(
+            "/address/{address}/transactions",
+            get(bitcoin_address_transactions_handler),
+        )
+        .route("/address/{address}/info", get(bitcoin_address_info_handler))
+        .route(
+            "/address/{address}/utxos",
+            get(bitcoin_address_utxos_handler),
+        )
         .route_service("/docs/openapi.json", openapi_service) // Serve JSON file
         .nest_service("/docs", swagger_ui_service) // Serve Swagger UI
         .method_not_allowed_fallback(method_not_allowed_handler)
@@ -0,0 +1,181 @@
+// Amounts are serialized in satoshis. They are deserialized from satoshis, as a number or a
+// string, or from a string with a unit, e.g. "0.001 BTC" or "100000 sat". Amounts above the
+// 21 million BTC supply are rejected.
+use std::str::FromStr;
+
+use bitcoin::{Amount, SignedAmount};
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AmountValue {
+    Sats(i64),
+    Text(String),
+}
+
+fn deserialize_signed<'de, D>(deserializer: D) -> Result<SignedAmount, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let amount = match AmountValue::deserialize(deserializer)? {
+        AmountValue::Sats(sats) => SignedAmount::from_sat(sats),
+        AmountValue::Text(text) => match text.trim().parse::<i64>() {
+            Ok(sats) => SignedAmount::from_sat(sats),
+            Err(_) => SignedAmount::from_str(text.trim())
+                .map_err(|err| de::Error::custom(format!("Invalid amount {}: {}", text, err)))?,
+        },
+    };
+
+    if amount.unsigned_abs() > Amount::MAX_MONEY {
+        return Err(de::Error::custom(format!(
+            "Amount {} is above the maximum of {}",
+            amount,
+            Amount::MAX_MONEY
+        )));
+    }
+
+    Ok(amount)
+}
+
+pub fn serialize<S>(amount: &Amount, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u64(amount.to_sat())
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Amount, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let amount = deserialize_signed(deserializer)?;
+    amount
+        .to_unsigned()
+        .map_err(|_| de::Error::custom(format!("Negative amount: {}", amount)))
+}
+
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S>(amount: &Option<Amount>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match amount {
+            Some(amount) => serializer.serialize_some(&amount.to_sat()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Amount>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super")] Amount);
+
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(amount)| amount))
+    }
+}
+
+pub mod signed {
+    use super::*;
+
+    pub fn serialize<S>(amount: &SignedAmount, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i64(amount.to_sat())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SignedAmount, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_signed(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::Query;
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Params {
+        #[serde(with = "super")]
+        amount: Amount,
+        #[serde(default, with = "super::option")]
+        min_value: Option<Amount>,
+        #[serde(default, with = "super::signed")]
+        balance: SignedAmount,
+    }
+
+    #[test]
+    fn test_deserialize_amount() {
+        for (json, sats) in [
+            (r#"{"amount": 100000}"#, 100_000),
+            (r#"{"amount": "100000"}"#, 100_000),
+            (r#"{"amount": "100000 sat"}"#, 100_000),
+            (r#"{"amount": "0.001 BTC"}"#, 100_000),
+            (r#"{"amount": "1.5 mBTC"}"#, 150_000),
+        ] {
+            let params: Params = serde_json::from_str(json).unwrap();
+            assert_eq!(params.amount, Amount::from_sat(sats), "{}", json);
+            assert_eq!(params.min_value, None);
+        }
+
+        for json in [
+            r#"{"amount": -1}"#,
+            r#"{"amount": "-0.001 BTC"}"#,
+            r#"{"amount": "0.001"}"#,
+            r#"{"amount": "0.000000001 BTC"}"#,
+            r#"{"amount": "1 ETH"}"#,
+            r#"{"amount": "22000000 BTC"}"#,
+            r#"{"amount": 2100000000000001}"#,
+            r#"{"amount": 9223372036854775807}"#,
+        ] {
+            assert!(serde_json::from_str::<Params>(json).is_err(), "{}", json);
+        }
+
+        let params: Params =
+            serde_json::from_str(r#"{"amount": 1, "min_value": "546 sat", "balance": "-0.5 BTC"}"#)
+                .unwrap();
+        assert_eq!(params.min_value, Some(Amount::from_sat(546)));
+        assert_eq!(params.balance, SignedAmount::from_sat(-50_000_000));
+
+        // The whole supply is the largest amount, in either direction
+        let params: Params =
+            serde_json::from_str(r#"{"amount": "21000000 BTC", "balance": "-21000000 BTC"}"#)
+                .unwrap();
+        assert_eq!(params.amount, Amount::MAX_MONEY);
+        assert_eq!(params.balance, -SignedAmount::MAX_MONEY);
+        assert!(
+            serde_json::from_str::<Params>(r#"{"amount": 1, "balance": "-22000000 BTC"}"#).is_err()
+        );
+
+        // Query strings only carry strings
+        let Query(params) = Query::<Params>::try_from_uri(
+            &"/utxos?amount=0.001%20BTC&min_value=546".parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(params.amount, Amount::from_sat(100_000));
+        assert_eq!(params.min_value, Some(Amount::from_sat(546)));
+    }
+
+    #[test]
+    fn test_serialize_amount() {
+        let params = Params {
+            amount: Amount::from_sat(100_000),
+            min_value: None,
+            balance: SignedAmount::from_sat(-5),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&params).unwrap(),
+            r#"{"amount":100000,"min_value":null,"balance":-5}"#
+        );
+    }
+}
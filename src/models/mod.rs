@@ -1,9 +1,16 @@
-use bitcoin::Transaction;
+use std::collections::HashMap;
+
+use bitcoin::{Amount, Denomination, SignedAmount, Transaction};
 use serde::{Deserialize, Serialize};
 
-use crate::{blockchains::bitcoin::response_models::BlockstreamUtxo, btc_api_error::BtcApiError};
+pub mod amount_serde;
+
+use crate::{
+    blockchains::bitcoin::{payment_uri::parse_payment_uri, response_models::BlockstreamUtxo},
+    btc_api_error::BtcApiError,
+};
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct NetworkFeeResponse {
     pub is_error: bool,
@@ -11,27 +18,215 @@ pub struct NetworkFeeResponse {
     pub error_msg: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+// Fee rates in sat/vB
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct NetworkFeeResponseData {
-    pub fastest_fee: i64,
-    pub half_hour_fee: i64,
-    pub hour_fee: i64,
-    pub economy_fee: i64,
-    pub minimum_fee: i64,
+    pub fastest_fee: f64,
+    pub half_hour_fee: f64,
+    pub hour_fee: f64,
+    pub economy_fee: f64,
+    pub minimum_fee: f64,
+    // Sources the fee rates were aggregated from
+    #[serde(default)]
+    pub sources: Vec<String>,
+    // Set when no source could be reached
+    #[serde(default)]
+    pub fallback: Option<FeeFallback>,
+    // Fee rate for the requested targetBlocks/targetMinutes
+    #[serde(default)]
+    pub target: Option<TargetFeeRate>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockResponse {
+    pub is_error: bool,
+    pub data: Option<BlockResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockResponseData {
+    pub hash: String,
+    pub height: u64,
+    pub timestamp: u64,
+    // Median time past
+    pub median_time: u64,
+    pub tx_count: u64,
+    pub size: u64,
+    pub weight: u64,
+    pub previous_block_hash: Option<String>,
+    // 1 for the tip
+    pub confirmations: u64,
+    // In sat/vB, only reported when bitcoind is configured
+    pub median_fee_rate: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTxidsResponse {
+    pub is_error: bool,
+    pub data: Option<BlockTxidsResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTxidsResponseData {
+    pub hash: String,
+    // In block order, the coinbase first
+    pub txids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MempoolResponse {
+    pub is_error: bool,
+    pub data: Option<MempoolResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MempoolResponseData {
+    // Number of transactions
+    pub count: u64,
+    pub vsize: u64,
+    // In satoshis, not reported by bitcoind before v0.20
+    pub total_fee: Option<u64>,
+    // From the highest fee rate down, empty when sourced from bitcoind
+    pub fee_histogram: Vec<FeeHistogramBucket>,
+    // In sat/vB, only reported by bitcoind
+    pub mempool_min_fee: Option<f64>,
+    pub source: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeHistogramBucket {
+    // In sat/vB, the lowest fee rate of the bucket
+    pub fee_rate: f64,
+    // Total vsize of the transactions in the bucket
+    pub vsize: u64,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkFeeParams {
+    pub target_blocks: Option<u16>,
+    // Converted to blocks of 10 minutes
+    pub target_minutes: Option<u32>,
+}
+
+impl NetworkFeeParams {
+    // Confirmation target in blocks, bitcoind estimates up to 1008 blocks
+    pub fn get_target_blocks(&self) -> Result<Option<u16>, BtcApiError> {
+        let target_blocks = match (self.target_blocks, self.target_minutes) {
+            (Some(_), Some(_)) => {
+                return Err(BtcApiError::InvalidFee(
+                    "Only one of targetBlocks and targetMinutes can be set".to_string(),
+                ))
+            }
+            (Some(target_blocks), None) => target_blocks as u32,
+            (None, Some(target_minutes)) => target_minutes.div_ceil(10),
+            (None, None) => return Ok(None),
+        };
+
+        if !(1..=1008).contains(&target_blocks) {
+            return Err(BtcApiError::InvalidFee(format!(
+                "Confirmation target of {} blocks is not between 1 and 1008",
+                target_blocks
+            )));
+        }
+
+        Ok(Some(target_blocks as u16))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetFeeRate {
+    pub target_blocks: u16,
+    // In sat/vB
+    pub fee_rate: f64,
+    pub confidence: FeeConfidence,
+    pub sources: Vec<TargetFeeRateSource>,
+    pub fallback: Option<FeeFallback>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetFeeRateSource {
+    pub source: String,
+    // Target the source had an estimate for, the closest one below the requested target
+    pub target_blocks: u16,
+    // In sat/vB
+    pub fee_rate: f64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum FeeConfidence {
+    // At least two sources agree
+    High,
+    // A single source
+    Medium,
+    // No source could be reached
+    Low,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum FeeFallback {
+    // The last aggregated fee rates, older than the cache ttl
+    LastKnown,
+    // The configured minimum fee rate
+    Floor,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ValidateTransactionHashParams {
     pub transaction_hash: String,
+    // Fiat currency the amounts are also converted to, e.g. USD
+    pub currency: Option<String>,
+    // Adds the amounts as BTC decimal strings
+    #[serde(default)]
+    pub include_btc: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidateTransactionHashesParams {
+    pub transaction_hashes: Vec<String>,
+    // Fiat currency the amounts are also converted to, e.g. USD
+    pub currency: Option<String>,
+    // Adds the amounts as BTC decimal strings
+    #[serde(default)]
+    pub include_btc: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct WalletBalanceParams {
     pub wallet_address: String,
+    // Fiat currency the balances are also converted to, e.g. USD
+    pub currency: Option<String>,
+    // Adds the balances as BTC decimal strings
+    #[serde(default)]
+    pub include_btc: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WalletBalancesParams {
+    pub wallet_addresses: Vec<String>,
+    // Fiat currency the balances are also converted to, e.g. USD
+    pub currency: Option<String>,
+    // Adds the balances as BTC decimal strings
+    #[serde(default)]
+    pub include_btc: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidateTransactionHashResponse {
     pub is_error: bool,
@@ -39,7 +234,32 @@ pub struct ValidateTransactionHashResponse {
     pub error_msg: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateTransactionHashesResponse {
+    pub is_error: bool,
+    pub data: Option<ValidateTransactionHashesResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateTransactionHashesResponseData {
+    // In the order of the requested transaction hashes
+    pub transactions: Vec<TransactionHashValidation>,
+}
+
+// Status of one transaction of the batch, a failed lookup does not fail the others
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionHashValidation {
+    pub transaction_hash: String,
+    pub is_error: bool,
+    pub data: Option<ValidateTransactionHashResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidateTransactionHashResponseData {
     pub txn_hash: String,
@@ -49,42 +269,364 @@ pub struct ValidateTransactionHashResponseData {
     // 1: Cancelled
     // 2: Pending
     pub txn_status_flag: u64,
+    // Number of blocks on top of (and including) the one that mined the txn, 0 if not mined.
+    // Unset for a mined txn when its block or the tip height is unavailable
+    pub confirmations: Option<u64>,
     pub txn_data: Option<TransactionData>,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum TxnStatus {
     Confirmed,
     Cancelled,
     Pending,
 }
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionData {
     pub block_index: Option<u64>,
     pub block_height: Option<u64>,
-    pub consumed_fees: u64,
-    pub txn_input_amount: u64,
-    pub txn_output_amount: u64,
+    // Unix time at which blockchain.info received the txn
+    pub txn_time: u64,
+    #[serde(with = "amount_serde")]
+    pub consumed_fees: Amount,
+    #[serde(with = "amount_serde")]
+    pub txn_input_amount: Amount,
+    #[serde(with = "amount_serde")]
+    pub txn_output_amount: Amount,
+    pub input_txns: Vec<AddressSpent>,
+    pub output_txns: Vec<AddressSpent>,
+    // Only set when a currency is requested, priced at the block time once mined, at txn_time
+    // otherwise
+    pub fiat: Option<FiatTransactionValue>,
+    // Only set when include_btc is requested
+    pub btc: Option<BtcTransactionValue>,
+}
+
+impl TransactionData {
+    pub fn add_btc_amounts(&mut self) {
+        self.btc = Some(BtcTransactionValue {
+            consumed_fees: self.consumed_fees.to_string_in(Denomination::Bitcoin),
+            txn_input_amount: self.txn_input_amount.to_string_in(Denomination::Bitcoin),
+            txn_output_amount: self.txn_output_amount.to_string_in(Denomination::Bitcoin),
+        });
+    }
+}
+
+// Amounts as BTC decimal strings, e.g. "0.001"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BtcTransactionValue {
+    pub consumed_fees: String,
+    pub txn_input_amount: String,
+    pub txn_output_amount: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FiatTransactionValue {
+    pub price: FiatPrice,
+    pub consumed_fees: f64,
+    pub txn_input_amount: f64,
+    pub txn_output_amount: f64,
+}
+
+// Price of 1 BTC
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FiatPrice {
+    pub currency: String,
+    pub price: f64,
+    // Unix timestamp the price was observed at
+    pub time: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddressTransactionsParams {
+    // Cursor returned as next_cursor by the previous page
+    pub last_seen_txid: Option<String>,
+    // Fiat currency code, e.g. USD, to also return the fiat values in
+    pub currency: Option<String>,
+    // Adds the amounts as BTC decimal strings
+    #[serde(default)]
+    pub include_btc: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransactionsResponse {
+    pub is_error: bool,
+    pub data: Option<AddressTransactionsResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransactionsResponseData {
+    pub address: String,
+    pub transactions: Vec<AddressTransaction>,
+    // Pass as last_seen_txid to fetch the next page, None if there are no more transactions
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransaction {
+    pub txn_hash: String,
+    pub txn_status: TxnStatus,
+    // Amount received by the address minus the amount it spent, in satoshis
+    #[serde(with = "amount_serde::signed")]
+    pub net_amount: SignedAmount,
+    #[serde(with = "amount_serde")]
+    pub consumed_fees: Amount,
+    pub block_height: Option<u64>,
+    pub block_time: Option<u64>,
     pub input_txns: Vec<AddressSpent>,
     pub output_txns: Vec<AddressSpent>,
+    // Only set when a currency is requested, priced at the block time once mined
+    pub fiat: Option<FiatAddressTransactionValue>,
+    // Only set when include_btc is requested
+    pub btc: Option<BtcAddressTransactionValue>,
+}
+
+impl AddressTransaction {
+    pub fn add_btc_amounts(&mut self) {
+        self.btc = Some(BtcAddressTransactionValue {
+            net_amount: self.net_amount.to_string_in(Denomination::Bitcoin),
+            consumed_fees: self.consumed_fees.to_string_in(Denomination::Bitcoin),
+        });
+    }
+}
+
+// Amounts as BTC decimal strings, e.g. "-0.001"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BtcAddressTransactionValue {
+    pub net_amount: String,
+    pub consumed_fees: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FiatAddressTransactionValue {
+    pub price: FiatPrice,
+    pub net_amount: f64,
+    pub consumed_fees: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddressUtxosParams {
+    pub min_confirmations: Option<u64>,
+    // Minimum UTXO value, in satoshis or with a unit
+    #[serde(default, with = "amount_serde::option")]
+    pub min_value: Option<Amount>,
+    // Adds the values as BTC decimal strings
+    #[serde(default)]
+    pub include_btc: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressUtxosResponse {
+    pub is_error: bool,
+    pub data: Option<AddressUtxosResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressUtxosResponseData {
+    pub address: String,
+    pub utxos: Vec<AddressUtxo>,
+    // Sum of the listed UTXOs in satoshis
+    #[serde(with = "amount_serde")]
+    pub total_value: Amount,
+    // Only set when include_btc is requested
+    pub btc: Option<BtcUtxosValue>,
+}
+
+impl AddressUtxosResponseData {
+    pub fn add_btc_amounts(&mut self) {
+        for utxo in &mut self.utxos {
+            utxo.btc = Some(BtcUtxoValue {
+                value: utxo.value.to_string_in(Denomination::Bitcoin),
+            });
+        }
+
+        self.btc = Some(BtcUtxosValue {
+            total_value: self.total_value.to_string_in(Denomination::Bitcoin),
+        });
+    }
+}
+
+// Values as BTC decimal strings, e.g. "0.001"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BtcUtxosValue {
+    pub total_value: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BtcUtxoValue {
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressUtxo {
+    pub txid: String,
+    pub vout: u32,
+    // Amount in satoshis
+    #[serde(with = "amount_serde")]
+    pub value: Amount,
+    pub block_height: Option<u64>,
+    // 0 when unconfirmed, None for a confirmed UTXO when the tip height is unavailable
+    pub confirmations: Option<u64>,
+    pub script_type: String,
+    pub state: UtxoState,
+    // Only set when include_btc is requested
+    pub btc: Option<BtcUtxoValue>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum UtxoState {
+    // Can be selected as an input by createTransaction
+    Spendable,
+    // Will not be selected by createTransaction, because it is still unconfirmed or it is
+    // locked by a transaction which was created but not broadcasted yet
+    Frozen,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct AddressSpent {
     pub address: String,
     // Amount in satoshis
-    pub amount: u64,
+    #[serde(with = "amount_serde")]
+    pub amount: Amount,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-// All fee in Satoshis
+// Amounts in satoshis or with a unit, e.g. "0.001 BTC" or "100000 sat"
 pub struct CreateTransactionParams {
     pub from_address: String,
+    // Can be omitted when a payment_uri is given
+    #[serde(default)]
     pub to_address: String,
-    pub amount: u64,
-    pub fee: u64,
+    // Can be omitted when the payment_uri has an amount
+    #[serde(default, with = "amount_serde")]
+    pub amount: Amount,
+    #[serde(with = "amount_serde")]
+    pub fee: Amount,
+    // BIP21 URI providing the to_address and the amount
+    #[serde(default)]
+    pub payment_uri: Option<String>,
+    // Outpoints ("txid:vout") which have to be spent by the transaction
+    #[serde(default)]
+    pub include_utxos: Vec<String>,
+    // Outpoints ("txid:vout") which must not be spent by the transaction
+    #[serde(default)]
+    pub exclude_utxos: Vec<String>,
+    // Absolute lock time (nLockTime), a block height below 500000000, a unix timestamp otherwise
+    #[serde(default)]
+    pub lock_time: Option<u32>,
+    // Relative lock times (BIP68) keyed by outpoint ("txid:vout"), only for the included UTXOs
+    #[serde(default)]
+    pub relative_lock_times: HashMap<String, RelativeLockTime>,
+    // Build the transaction even if its lock times are not satisfied at the tip yet. The
+    // mempool rejects it until they are, so it has to be broadcasted later.
+    #[serde(default)]
+    pub allow_non_final: bool,
+    // Data embedded in an additional zero value OP_RETURN output
+    #[serde(default)]
+    pub op_return: Option<OpReturnData>,
+    // Adds the amounts of the response as BTC decimal strings
+    #[serde(default)]
+    pub include_btc: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+// Amounts in satoshis or with a unit, e.g. "0.001 BTC" or "100000 sat"
+pub struct EstimateTransactionFeeParams {
+    pub from_address: String,
+    pub to_address: String,
+    #[serde(with = "amount_serde")]
+    pub amount: Amount,
+    #[serde(default)]
+    pub include_utxos: Vec<String>,
+    #[serde(default)]
+    pub exclude_utxos: Vec<String>,
+    #[serde(default)]
+    pub op_return: Option<OpReturnData>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateTransactionFeeResponse {
+    pub is_error: bool,
+    pub data: Option<EstimateTransactionFeeResponseData>,
+    pub error_msg: Option<String>,
+}
+
+// Quote at each networkFee rate
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateTransactionFeeResponseData {
+    pub fastest_fee: TransactionFeeQuote,
+    pub half_hour_fee: TransactionFeeQuote,
+    pub hour_fee: TransactionFeeQuote,
+    pub economy_fee: TransactionFeeQuote,
+    pub minimum_fee: TransactionFeeQuote,
+    // Set when the network fee rates are a fallback
+    pub fee_fallback: Option<FeeFallback>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionFeeQuote {
+    // In sat/vB
+    pub fee_rate: f64,
+    // The fee to pass to createTransaction, None when the funds do not cover the amount and fee
+    pub fee: Option<u64>,
+    // Estimated size once signed
+    pub vsize: Option<u64>,
+    pub input_count: Option<usize>,
+    // Satoshis missing to cover the amount and the fee
+    pub missing_amount: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OpReturnData {
+    Hex(String),
+    // Embedded as UTF-8 bytes
+    Text(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RelativeLockTime {
+    // Blocks after the UTXO was mined
+    Blocks(u16),
+    // Seconds after the median time past of the block before the one which mined the UTXO,
+    // rounded up to a multiple of 512
+    Seconds(u32),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeLockInfo {
+    pub lock_time: u32,
+    pub tip_height: u64,
+    pub tip_median_time: u64,
+    // First block height at which the transaction can be mined
+    pub final_at_height: Option<u64>,
+    // Median time past from which the transaction can be mined
+    pub final_at_time: Option<u64>,
+    // Whether the transaction can be broadcasted now, only false when allow_non_final is set
+    pub is_final: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -101,10 +643,75 @@ pub struct CreateTransactionResponseData {
     pub unsigned_raw_txn: Transaction,
     pub used_utxos: Vec<BlockstreamUtxo>,
     pub txn_size: u64,
+    // Sent to to_address, in satoshis
+    #[serde(with = "amount_serde")]
+    pub amount: Amount,
+    #[serde(with = "amount_serde")]
+    pub fee: Amount,
+    // Paid back to from_address, in satoshis
+    #[serde(with = "amount_serde")]
+    pub change_amount: Amount,
+    // Fee over the estimated signed vsize in sat/vB, None when the input type is not supported
+    // by the estimation
+    pub fee_rate: Option<f64>,
+    // The used UTXOs stay locked for this long unless the transaction is broadcasted or they
+    // are released
+    pub utxo_lock_ttl_secs: u64,
+    // Only set when a lock time was requested
+    pub time_lock: Option<TimeLockInfo>,
+    // Only set when include_btc is requested
+    pub btc: Option<BtcCreatedTransactionValue>,
+}
+
+impl CreateTransactionResponseData {
+    pub fn add_btc_amounts(&mut self) {
+        self.btc = Some(BtcCreatedTransactionValue {
+            amount: self.amount.to_string_in(Denomination::Bitcoin),
+            fee: self.fee.to_string_in(Denomination::Bitcoin),
+            change_amount: self.change_amount.to_string_in(Denomination::Bitcoin),
+        });
+    }
+}
+
+// Amounts as BTC decimal strings, e.g. "0.001"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BtcCreatedTransactionValue {
+    pub amount: String,
+    pub fee: String,
+    pub change_amount: String,
 }
 
 impl CreateTransactionParams {
-    pub fn validate(self) -> Result<CreateTransactionParams, BtcApiError> {
+    pub fn validate(mut self) -> Result<CreateTransactionParams, BtcApiError> {
+        if let Some(payment_uri) = &self.payment_uri {
+            let payment_uri = parse_payment_uri(payment_uri)?;
+
+            if !self.to_address.is_empty() && self.to_address != payment_uri.address {
+                return Err(BtcApiError::InvalidPaymentUri(format!(
+                    "to_address {} does not match the payment URI address {}",
+                    self.to_address, payment_uri.address
+                )));
+            }
+            self.to_address = payment_uri.address;
+
+            match payment_uri.amount {
+                Some(amount) if self.amount != Amount::ZERO && self.amount != amount => {
+                    return Err(BtcApiError::InvalidPaymentUri(format!(
+                        "amount {} does not match the payment URI amount {}",
+                        self.amount, amount
+                    )));
+                }
+                Some(amount) => self.amount = amount,
+                None if self.amount == Amount::ZERO => {
+                    return Err(BtcApiError::InvalidPaymentUri(
+                        "The payment URI has no amount, amount is required".to_string(),
+                    ));
+                }
+                None => {}
+            }
+        }
+
         if self.fee >= self.amount {
             Err(BtcApiError::InvalidFee(format!(
                 "Fee {} is greater than amount {}",
@@ -116,9 +723,218 @@ impl CreateTransactionParams {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressInfoResponse {
+    pub is_error: bool,
+    pub data: Option<AddressInfoResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressInfoResponseData {
+    pub address: String,
+    // Valid on the configured network
+    pub is_valid: bool,
+    pub invalid_reason: Option<AddressInvalidReason>,
+    pub reason_msg: Option<String>,
+    // The configured network
+    pub network: String,
+    // Networks the address can be used on
+    pub valid_networks: Vec<String>,
+    // P2PKH, P2SH, P2WPKH, P2WSH or P2TR, the rest is only set when the address can be parsed
+    pub address_type: Option<String>,
+    pub witness_version: Option<u8>,
+    pub witness_program: Option<String>,
+    pub script_pubkey: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum AddressInvalidReason {
+    BadChecksum,
+    // Valid address of another network
+    WrongNetwork,
+    // Bech32 prefix which is not bitcoin's
+    UnknownHrp,
+    InvalidEncoding,
+    InvalidLength,
+    InvalidWitnessProgram,
+    // Base58 version byte which is not bitcoin's
+    InvalidPrefix,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ParsePaymentUriParams {
+    pub uri: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreatePaymentUriParams {
+    pub address: String,
+    // In satoshis
+    #[serde(default, with = "amount_serde::option")]
+    pub amount: Option<Amount>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+    // BOLT11 invoice
+    pub lightning: Option<String>,
+    // Payjoin endpoint
+    pub pj: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentUriResponse {
+    pub is_error: bool,
+    pub data: Option<PaymentUriData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentUriData {
+    pub uri: String,
+    pub address: String,
+    // In satoshis
+    #[serde(default, with = "amount_serde::option")]
+    pub amount: Option<Amount>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+    pub lightning: Option<String>,
+    pub pj: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum MessageSignatureFormat {
+    // Legacy "Bitcoin Signed Message" signatures, P2PKH and (nested) P2WPKH only
+    Bip137,
+    // BIP322 simple signatures, P2WPKH and P2TR
+    Bip322,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignMessageParams {
+    pub address: String,
+    pub message: String,
+    // Defaults to BIP322 for taproot addresses and BIP137 otherwise
+    pub format: Option<MessageSignatureFormat>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignMessageResponse {
+    pub is_error: bool,
+    pub data: Option<SignMessageResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignMessageResponseData {
+    pub address: String,
+    pub message: String,
+    // Base64 encoded
+    pub signature: String,
+    pub format: MessageSignatureFormat,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VerifyMessageParams {
+    pub address: String,
+    pub message: String,
+    // Base64 encoded BIP137 or BIP322 simple signature
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyMessageResponse {
+    pub is_error: bool,
+    pub data: Option<VerifyMessageResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyMessageResponseData {
+    pub address: String,
+    pub is_valid: bool,
+    pub format: MessageSignatureFormat,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+// Amounts in satoshis or with a unit, e.g. "0.001 BTC" or "100000 sat"
+pub struct ConsolidateTransactionParams {
+    pub address: String,
+    // Only confirmed UTXOs with a value below this threshold are consolidated
+    #[serde(with = "amount_serde")]
+    pub value_threshold: Amount,
+    #[serde(default = "default_consolidation_max_inputs")]
+    pub max_inputs: usize,
+    // In sat/vB, caps the economy fee rate of the network that is paid otherwise
+    pub max_fee_rate: Option<u64>,
+    // Adds the fee and the output amount as BTC decimal strings
+    #[serde(default)]
+    pub include_btc: bool,
+}
+
+fn default_consolidation_max_inputs() -> usize {
+    100
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsolidateTransactionResponse {
+    pub is_error: bool,
+    pub data: Option<ConsolidateTransactionResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsolidateTransactionResponseData {
+    pub unsigned_raw_txn: Transaction,
+    pub used_utxos: Vec<BlockstreamUtxo>,
+    // Estimated size of the signed transaction in vBytes
+    pub txn_size: u64,
+    // In sat/vB
+    pub fee_rate: u64,
+    #[serde(with = "amount_serde")]
+    pub fee: Amount,
+    // Value of the single consolidated output
+    #[serde(with = "amount_serde")]
+    pub output_amount: Amount,
+    pub utxo_lock_ttl_secs: u64,
+    // Only set when include_btc is requested
+    pub btc: Option<BtcConsolidationValue>,
+}
+
+impl ConsolidateTransactionResponseData {
+    pub fn add_btc_amounts(&mut self) {
+        self.btc = Some(BtcConsolidationValue {
+            fee: self.fee.to_string_in(Denomination::Bitcoin),
+            output_amount: self.output_amount.to_string_in(Denomination::Bitcoin),
+        });
+    }
+}
+
+// Amounts as BTC decimal strings, e.g. "0.001"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BtcConsolidationValue {
+    pub fee: String,
+    pub output_amount: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BroadcastTransactionParams {
     pub signed_raw_txn: String,
+    // Skip the local policy checks (fee sanity, dust, spent inputs) before broadcasting
+    #[serde(default)]
+    pub force: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -127,12 +943,205 @@ pub struct BroadcastTransactionResponse {
     pub is_error: bool,
     pub data: Option<BroadcastTransactionResponseData>,
     pub error_msg: Option<String>,
+    // Machine readable reason when the transaction was rejected
+    pub error_code: Option<BroadcastErrorCode>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum BroadcastErrorCode {
+    // An input is unknown or already spent
+    MissingInputs,
+    // The transaction is already confirmed
+    AlreadyInChain,
+    // The transaction is already in the mempool
+    AlreadyInMempool,
+    // The fee does not pay for the transaction or its replacement
+    InsufficientFee,
+    // The fee rate is below the node's minimum relay fee
+    MinRelayFeeNotMet,
+    // The fee rate is below the minimum of a full mempool
+    MempoolMinFeeNotMet,
+    // The transaction is not standard by the node's policy (script, size, version, ...)
+    NonStandard,
+    // An output is below the dust limit
+    Dust,
+    // A conflicting mempool transaction can not be replaced
+    RbfRejected,
+    // Too many unconfirmed ancestors or descendants
+    TooLongMempoolChain,
+    // The lock time or a relative lock of an input is not reached yet
+    NonFinal,
+    // A signature or script does not verify
+    ScriptVerifyFailed,
+    // The fee is above the node's maximum
+    MaxFeeExceeded,
+    // The raw transaction could not be decoded
+    DecodeFailed,
+    // Rejected by the local checks before the broadcast
+    PolicyViolation,
+    Unknown,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BroadcastTransactionResponseData {
     pub txn_hash: String,
     pub txn_hash_url: String,
+    // Outcome of every configured broadcast provider
+    pub provider_results: Vec<ProviderBroadcastResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProviderBroadcastResult {
+    pub provider: String,
+    // The provider has the transaction, also true when it already was in its mempool
+    pub accepted: bool,
+    pub error_msg: Option<String>,
+    pub error_code: Option<BroadcastErrorCode>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubmitPackageParams {
+    // Parents first, children last
+    pub signed_raw_txns: Vec<String>,
+    // Skip the local policy checks when the package is broadcasted one transaction at a time
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitPackageResponse {
+    pub is_error: bool,
+    pub data: Option<SubmitPackageResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitPackageResponseData {
+    // bitcoind (submitpackage) or sequential
+    pub submitted_via: String,
+    pub all_accepted: bool,
+    // Package level message of bitcoind
+    pub package_msg: Option<String>,
+    // In the order of the submitted transactions
+    pub txn_results: Vec<PackageTxnResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageTxnResult {
+    pub txid: String,
+    pub accepted: bool,
+    pub error_msg: Option<String>,
+    pub error_code: Option<BroadcastErrorCode>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TestTransactionParams {
+    pub signed_raw_txn: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestTransactionResponse {
+    pub is_error: bool,
+    pub data: Option<TestTransactionResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestTransactionResponseData {
+    pub txid: String,
+    // Whether the transaction would be accepted by a broadcast
+    pub allowed: bool,
+    pub reject_reason: Option<String>,
+    pub error_code: Option<BroadcastErrorCode>,
+    pub vsize: u64,
+    // Only available when the spent outputs are known, in satoshis
+    pub fee: Option<u64>,
+    // In sat/vB
+    pub fee_rate: Option<f64>,
+    // bitcoind (testmempoolaccept) or localPolicy
+    pub checked_by: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DecodeTransactionParams {
+    pub raw_txn: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodeTransactionResponse {
+    pub is_error: bool,
+    pub data: Option<DecodeTransactionResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodeTransactionResponseData {
+    pub txid: String,
+    pub wtxid: String,
+    pub version: i32,
+    pub lock_time: u32,
+    pub size: u64,
+    pub weight: u64,
+    pub vsize: u64,
+    // Whether any input signals BIP125 replaceability
+    pub is_rbf: bool,
+    pub inputs: Vec<DecodedInput>,
+    pub outputs: Vec<DecodedOutput>,
+    // Only available when all the previous outputs could be fetched, in satoshis
+    pub fee: Option<u64>,
+    // In sat/vB
+    pub fee_rate: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedInput {
+    pub txid: String,
+    pub vout: u32,
+    pub sequence: u32,
+    pub script_sig: String,
+    pub witness: Vec<String>,
+    // Spent previous output, only available when it could be fetched
+    pub prevout: Option<DecodedOutput>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedOutput {
+    // Amount in satoshis
+    pub value: u64,
+    pub script_pubkey: String,
+    // None for scripts without an address form, e.g. OP_RETURN
+    pub address: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReleaseUtxosParams {
+    // Outpoints ("txid:vout") to unlock
+    pub utxos: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseUtxosResponse {
+    pub is_error: bool,
+    pub data: Option<ReleaseUtxosResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseUtxosResponseData {
+    // Outpoints which were locked and are now available again
+    pub released_utxos: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -149,22 +1158,173 @@ pub struct WalletBalanceResponse {
     pub error_msg: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletBalancesResponse {
+    pub is_error: bool,
+    pub data: Option<WalletBalancesResponseData>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletBalancesResponseData {
+    // In the order of the requested addresses
+    pub balances: Vec<AddressWalletBalance>,
+}
+
+// Balance of one address of the batch, a failed address does not fail the others
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressWalletBalance {
+    pub wallet_address: String,
+    pub is_error: bool,
+    pub data: Option<WalletBalanceResponseData>,
+    pub error_msg: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct WalletBalanceResponseData {
-    pub confirmed_balance: i64,
-    pub unconfirmed_balance: i64,
-    pub total_balance: i64,
+    #[serde(with = "amount_serde::signed")]
+    pub confirmed_balance: SignedAmount,
+    #[serde(with = "amount_serde::signed")]
+    pub unconfirmed_balance: SignedAmount,
+    #[serde(with = "amount_serde::signed")]
+    pub total_balance: SignedAmount,
+    // Only set when a currency is requested, priced at the current price
+    pub fiat: Option<FiatBalance>,
+    // Only set when include_btc is requested
+    pub btc: Option<BtcBalance>,
+}
+
+impl WalletBalanceResponseData {
+    pub fn add_btc_amounts(&mut self) {
+        self.btc = Some(BtcBalance {
+            confirmed_balance: self.confirmed_balance.to_string_in(Denomination::Bitcoin),
+            unconfirmed_balance: self.unconfirmed_balance.to_string_in(Denomination::Bitcoin),
+            total_balance: self.total_balance.to_string_in(Denomination::Bitcoin),
+        });
+    }
+}
+
+// Balances as BTC decimal strings, e.g. "-0.001"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BtcBalance {
+    pub confirmed_balance: String,
+    pub unconfirmed_balance: String,
+    pub total_balance: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FiatBalance {
+    pub price: FiatPrice,
+    pub confirmed_balance: f64,
+    pub unconfirmed_balance: f64,
+    pub total_balance: f64,
 }
 mod test {
 
+    #[test]
+    fn test_network_fee_params_target_blocks() {
+        let params: crate::models::NetworkFeeParams =
+            serde_json::from_str(r#"{"targetMinutes": 45}"#).unwrap();
+        assert_eq!(params.get_target_blocks().unwrap(), Some(5));
+
+        let params: crate::models::NetworkFeeParams =
+            serde_json::from_str(r#"{"targetBlocks": 3}"#).unwrap();
+        assert_eq!(params.get_target_blocks().unwrap(), Some(3));
+
+        let params = crate::models::NetworkFeeParams::default();
+        assert_eq!(params.get_target_blocks().unwrap(), None);
+
+        for json in [
+            r#"{"targetBlocks": 0}"#,
+            r#"{"targetBlocks": 2000}"#,
+            r#"{"targetBlocks": 3, "targetMinutes": 30}"#,
+        ] {
+            let params: crate::models::NetworkFeeParams = serde_json::from_str(json).unwrap();
+            assert!(params.get_target_blocks().is_err());
+        }
+    }
+
     #[test]
     fn test_deserialize_create_transaction_params() {
         let json = r#"{"from_address": "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", "to_address": "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", "amount": 100000000, "fee": 100000000}"#;
         let params: crate::models::CreateTransactionParams = serde_json::from_str(json).unwrap();
         assert_eq!(params.from_address, "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa");
         assert_eq!(params.to_address, "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa");
-        assert_eq!(params.amount, 100000000);
-        assert_eq!(params.fee, 100000000);
+        assert_eq!(params.amount, bitcoin::Amount::from_sat(100000000));
+        assert_eq!(params.fee, bitcoin::Amount::from_sat(100000000));
+        assert!(!params.allow_non_final);
+
+        // Amounts can also be given with a unit
+        let json = r#"{"from_address": "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", "to_address": "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", "amount": "0.001 BTC", "fee": "500 sat"}"#;
+        let params: crate::models::CreateTransactionParams = serde_json::from_str(json).unwrap();
+        assert_eq!(params.amount, bitcoin::Amount::from_sat(100000));
+        assert_eq!(params.fee, bitcoin::Amount::from_sat(500));
+    }
+
+    #[test]
+    fn test_wallet_balance_btc_amounts() {
+        let mut wallet_balance = crate::models::WalletBalanceResponseData {
+            confirmed_balance: bitcoin::SignedAmount::from_sat(150000),
+            unconfirmed_balance: bitcoin::SignedAmount::from_sat(-50000),
+            total_balance: bitcoin::SignedAmount::from_sat(100000),
+            fiat: None,
+            btc: None,
+        };
+        wallet_balance.add_btc_amounts();
+
+        let json = serde_json::to_value(&wallet_balance).unwrap();
+        assert_eq!(json["unconfirmedBalance"], -50000);
+        assert_eq!(json["btc"]["confirmedBalance"], "0.0015");
+        assert_eq!(json["btc"]["unconfirmedBalance"], "-0.0005");
+        assert_eq!(json["btc"]["totalBalance"], "0.001");
+    }
+
+    #[test]
+    fn test_address_utxos_btc_amounts() {
+        let mut address_utxos = crate::models::AddressUtxosResponseData {
+            address: "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+            utxos: vec![crate::models::AddressUtxo {
+                txid: "cf63765034a06d6afb13ff7bf7bd5c4a6959188cf167c85aa17bb22a4c4b33b2"
+                    .to_string(),
+                vout: 0,
+                value: bitcoin::Amount::from_sat(30000),
+                block_height: None,
+                confirmations: Some(0),
+                script_type: "p2wpkh".to_string(),
+                state: crate::models::UtxoState::Frozen,
+                btc: None,
+            }],
+            total_value: bitcoin::Amount::from_sat(30000),
+            btc: None,
+        };
+        address_utxos.add_btc_amounts();
+
+        let json = serde_json::to_value(&address_utxos).unwrap();
+        assert_eq!(json["totalValue"], 30000);
+        assert_eq!(json["btc"]["totalValue"], "0.0003");
+        assert_eq!(json["utxos"][0]["btc"]["value"], "0.0003");
+    }
+
+    #[test]
+    fn test_validate_create_transaction_params_from_payment_uri() {
+        let json = r#"{"from_address": "tb1qc64e7hqlt57kaz5nclpurg9k7r3d35lpyxr3rl", "fee": 500, "payment_uri": "bitcoin:tb1q4a0umk5zxq302kfzqg5qfxta5jcp46ws6r0pjy?amount=0.0001"}"#;
+        let params: crate::models::CreateTransactionParams = serde_json::from_str(json).unwrap();
+        let params = params.validate().unwrap();
+        assert_eq!(
+            params.to_address,
+            "tb1q4a0umk5zxq302kfzqg5qfxta5jcp46ws6r0pjy"
+        );
+        assert_eq!(params.amount, bitcoin::Amount::from_sat(10000));
+
+        // The explicit amount has to match the payment URI
+        let json = r#"{"from_address": "tb1qc64e7hqlt57kaz5nclpurg9k7r3d35lpyxr3rl", "amount": 20000, "fee": 500, "payment_uri": "bitcoin:tb1q4a0umk5zxq302kfzqg5qfxta5jcp46ws6r0pjy?amount=0.0001"}"#;
+        let params: crate::models::CreateTransactionParams = serde_json::from_str(json).unwrap();
+        assert!(params.validate().is_err());
     }
 }